@@ -77,5 +77,172 @@ pub mod hci {
             }?;
             return Ok(GapData { data_type, data: bytes[2..(data_size + 1)].to_vec() });
         }
+
+        /// Serializes this GapData as the `[len][type][data...]` triple used in EIR/advertising
+        /// payloads, where `len` covers `data_type` plus `data`.
+        pub fn encode(&self) -> std::result::Result<Vec<u8>, String> {
+            let len = self.data.len() + 1;
+            if len > u8::MAX as usize {
+                return Err(format!("data too large to encode: {} bytes", self.data.len()));
+            }
+
+            let mut encoded = Vec::with_capacity(len + 1);
+            encoded.push(len as u8);
+            encoded.push(self.data_type as u8);
+            encoded.extend_from_slice(&self.data);
+            Ok(encoded)
+        }
+
+        /// Parses every GAP data structure out of an EIR or advertising-data buffer, stopping
+        /// cleanly at the zero-padding that terminates EIR blobs.
+        pub fn parse_all(bytes: &[u8]) -> std::result::Result<Vec<Self>, String> {
+            let mut result = vec![];
+            let mut i = 0;
+            while i < bytes.len() {
+                let len = bytes[i] as usize;
+                if len == 0 {
+                    // Zero-padding terminator (or end of EIR).
+                    break;
+                }
+
+                if i + 1 + len > bytes.len() {
+                    return Err(format!(
+                        "size {} at offset {} is bigger than remaining length {}",
+                        len,
+                        i,
+                        bytes.len() - i - 1
+                    ));
+                }
+
+                let data_type = GapDataType::try_from(bytes[i + 1])
+                    .map_err(|_| format!("can't parse data type {}", bytes[i + 1]))?;
+                let data = bytes[(i + 2)..(i + 1 + len)].to_vec();
+                result.push(GapData { data_type, data });
+
+                i += 1 + len;
+            }
+
+            Ok(result)
+        }
+
+        pub fn flags(flags: u8) -> Self {
+            GapData { data_type: GapDataType::Flags, data: vec![flags] }
+        }
+
+        pub fn complete_local_name(name: &str) -> Self {
+            GapData { data_type: GapDataType::CompleteLocalName, data: name.as_bytes().to_vec() }
+        }
+
+        pub fn shortened_local_name(name: &str) -> Self {
+            GapData { data_type: GapDataType::ShortenedLocalName, data: name.as_bytes().to_vec() }
+        }
+
+        pub fn complete_service_uuids_16(uuids: &[u16]) -> Self {
+            GapData {
+                data_type: GapDataType::CompleteList16BitUuids,
+                data: uuids.iter().flat_map(|uuid| uuid.to_le_bytes()).collect(),
+            }
+        }
+
+        pub fn manufacturer_specific_data(company_id: u16, data: &[u8]) -> Self {
+            let mut bytes = company_id.to_le_bytes().to_vec();
+            bytes.extend_from_slice(data);
+            GapData { data_type: GapDataType::ManufacturerSpecificData, data: bytes }
+        }
+
+        /// Returns the decoded name for `CompleteLocalName`/`ShortenedLocalName`, if this is one.
+        pub fn as_local_name(&self) -> Option<String> {
+            match self.data_type {
+                GapDataType::CompleteLocalName | GapDataType::ShortenedLocalName => {
+                    Some(String::from_utf8_lossy(&self.data).into_owned())
+                }
+                _ => None,
+            }
+        }
+
+        /// Returns the list of 16-bit service UUIDs, if this is a complete or incomplete 16-bit
+        /// service UUID list.
+        pub fn as_service_uuids_16(&self) -> Option<Vec<u16>> {
+            if self.data_type != GapDataType::CompleteList16BitUuids
+                && self.data_type != GapDataType::IncompleteList16BitUuids
+            {
+                return None;
+            }
+            Some(self.data.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect())
+        }
+
+        /// Returns the list of 32-bit service UUIDs, if this is a complete or incomplete 32-bit
+        /// service UUID list.
+        pub fn as_service_uuids_32(&self) -> Option<Vec<u32>> {
+            if self.data_type != GapDataType::CompleteList32BitUuids
+                && self.data_type != GapDataType::IncompleteList32BitUuids
+            {
+                return None;
+            }
+            Some(
+                self.data
+                    .chunks_exact(4)
+                    .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                    .collect(),
+            )
+        }
+
+        /// Returns the list of 128-bit service UUIDs, if this is a complete or incomplete 128-bit
+        /// service UUID list.
+        pub fn as_service_uuids_128(&self) -> Option<Vec<u128>> {
+            if self.data_type != GapDataType::CompleteList128BitUuids
+                && self.data_type != GapDataType::IncompleteList128BitUuids
+            {
+                return None;
+            }
+            Some(
+                self.data
+                    .chunks_exact(16)
+                    .map(|c| u128::from_le_bytes(c.try_into().unwrap()))
+                    .collect(),
+            )
+        }
+
+        /// Returns `(uuid, data)` for 16-bit UUID service data, if this is one.
+        pub fn as_service_data_16(&self) -> Option<(u16, Vec<u8>)> {
+            if self.data_type != GapDataType::ServiceData16BitUuid || self.data.len() < 2 {
+                return None;
+            }
+            let uuid = u16::from_le_bytes([self.data[0], self.data[1]]);
+            Some((uuid, self.data[2..].to_vec()))
+        }
+
+        /// Returns the advertised TX power level in dBm, if this is one.
+        pub fn as_tx_power_level(&self) -> Option<i8> {
+            if self.data_type != GapDataType::TxPowerLevel || self.data.is_empty() {
+                return None;
+            }
+            Some(self.data[0] as i8)
+        }
+
+        /// Returns the external appearance value, if this is one.
+        pub fn as_appearance(&self) -> Option<u16> {
+            if self.data_type != GapDataType::Appearance || self.data.len() < 2 {
+                return None;
+            }
+            Some(u16::from_le_bytes([self.data[0], self.data[1]]))
+        }
+
+        /// Returns the advertising flags byte, if this is one.
+        pub fn as_flags(&self) -> Option<u8> {
+            if self.data_type != GapDataType::Flags || self.data.is_empty() {
+                return None;
+            }
+            Some(self.data[0])
+        }
+
+        /// Returns `(company_id, data)` for manufacturer-specific data, if this is one.
+        pub fn as_manufacturer_specific_data(&self) -> Option<(u16, Vec<u8>)> {
+            if self.data_type != GapDataType::ManufacturerSpecificData || self.data.len() < 2 {
+                return None;
+            }
+            let company_id = u16::from_le_bytes([self.data[0], self.data[1]]);
+            Some((company_id, self.data[2..].to_vec()))
+        }
     }
 }
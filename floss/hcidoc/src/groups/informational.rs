@@ -13,7 +13,10 @@ use hcidoc_packets::hci::{
     Address, CommandChild, DisconnectReason, ErrorCode, EventChild, GapData, GapDataType,
     LeMetaEventChild,
 };
-use hcidoc_packets::l2cap::{ConnectionResponseResult, ControlChild};
+use hcidoc_packets::l2cap::{
+    ConnectionResponseResult, ControlChild, LeCreditBasedConnectionResponseResult,
+};
+use serde_json::{json, Value};
 
 /// Valid values are in the range 0x0000-0x0EFF.
 type ConnectionHandle = u16;
@@ -23,6 +26,26 @@ type Cid = u16;
 
 const INVALID_TS: NaiveDateTime = NaiveDateTime::MAX;
 
+/// The fixed classic L2CAP PSM that RFCOMM multiplexes all its DLCIs over.
+const RFCOMM_PSM: Psm = 3;
+
+// RFCOMM control field opcodes (GSM 07.10 framing), with the P/F bit already masked off.
+const RFCOMM_CONTROL_SABM: u8 = 0x2f;
+const RFCOMM_CONTROL_DISC: u8 = 0x43;
+const RFCOMM_CONTROL_UA: u8 = 0x63;
+
+/// Parses the address and control bytes of an RFCOMM frame, returning `(dlci, control)` with the
+/// control field's P/F bit masked off. The DLCI is encoded in the top 6 bits of the address byte.
+fn parse_rfcomm_address_and_control(payload: &[u8]) -> Option<(u8, u8)> {
+    if payload.len() < 2 {
+        return None;
+    }
+    let address = payload[0];
+    let control = payload[1] & !0x10;
+    let dlci = address >> 2;
+    Some((dlci, control))
+}
+
 fn print_timestamps_and_initiator(
     start: NaiveDateTime,
     start_initiator: InitiatorType,
@@ -46,6 +69,62 @@ fn print_timestamps_and_initiator(
     );
 }
 
+/// `null` for `INVALID_TS`, otherwise the timestamp rendered the same way the text report does.
+fn ts_to_json(ts: NaiveDateTime) -> Value {
+    if ts == INVALID_TS {
+        Value::Null
+    } else {
+        json!(ts.to_string())
+    }
+}
+
+/* Sort when displaying the addresses, from the most to the least important:
+ * (1) Device with connections > Device without connections
+ * (2) Device with known name or device class > Device with neither
+ * (3) BREDR > LE > Dual
+ * (4) Name, lexicographically (case sensitive)
+ * (5) Address, alphabetically
+ *
+ * Shared between the text report and the JSON report so both list devices in the same order.
+ */
+fn sort_addresses(a: &DeviceInformation, b: &DeviceInformation) -> Ordering {
+    let a_empty = a.acls[&Transport::BREDR].is_empty() && a.acls[&Transport::LE].is_empty();
+    let b_empty = b.acls[&Transport::BREDR].is_empty() && b.acls[&Transport::LE].is_empty();
+    let connection_order = a_empty.cmp(&b_empty);
+    if connection_order != Ordering::Equal {
+        return connection_order;
+    }
+
+    let is_unknown = |d: &DeviceInformation| d.names.is_empty() && d.device_class() == DeviceClass::Unknown;
+    let known_name_order = is_unknown(a).cmp(&is_unknown(b));
+    if known_name_order != Ordering::Equal {
+        return known_name_order;
+    }
+
+    let address_type_order = a.address_type.cmp(&b.address_type);
+    if address_type_order != Ordering::Equal {
+        return address_type_order;
+    }
+
+    let a_name = format!("{}", DeviceInformation::print_names(&a.names));
+    let b_name = format!("{}", DeviceInformation::print_names(&b.names));
+    let name_order = a_name.cmp(&b_name);
+    if name_order != Ordering::Equal {
+        return name_order;
+    }
+
+    let a_address = <[u8; 6]>::from(a.address);
+    let b_address = <[u8; 6]>::from(b.address);
+    for i in (0..6).rev() {
+        let address_order = a_address[i].cmp(&b_address[i]);
+        if address_order != Ordering::Equal {
+            return address_order;
+        }
+    }
+    // This shouldn't be executed
+    return Ordering::Equal;
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, PartialOrd, Ord)]
 enum AddressType {
     None,
@@ -119,31 +198,291 @@ impl fmt::Display for InitiatorType {
     }
 }
 
-#[derive(Copy, Clone)]
-enum AclState {
-    None,
+/// A minimal state machine: `transition` computes the next state for a legal `(state, input)`
+/// pair, returning `None` when `input` isn't valid from `state`. `output` optionally describes a
+/// side effect to run on a legal transition, and defaults to none.
+trait StateMachine {
+    type State;
+    type Input;
+
+    fn transition(state: &Self::State, input: &Self::Input) -> Option<Self::State>;
+
+    fn output(_state: &Self::State, _input: &Self::Input) -> Option<String> {
+        None
+    }
+}
+
+/// The lifecycle of one ACL connection (per transport), as driven by the HCI commands/events this
+/// rule observes. Replaces the old flat `AclState`, which could only represent
+/// None/Initiating/Accepting/Connected and had no way to validate that an event made sense for
+/// the state the connection was actually in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum AclLifecycleState {
+    Closed,
     Initiating,
     Accepting,
     Connected,
+    Authenticating,
+    Encrypting,
+    RoleSwitching,
+    Disconnecting,
 }
 
-impl AclState {
+impl AclLifecycleState {
     fn get_connection_initiator(&self) -> InitiatorType {
         match self {
-            AclState::Initiating => InitiatorType::Host,
-            AclState::Accepting => InitiatorType::Peer,
+            AclLifecycleState::Initiating => InitiatorType::Host,
+            AclLifecycleState::Accepting => InitiatorType::Peer,
             _ => InitiatorType::Unknown,
         }
     }
 }
 
+/// The HCI commands/events that drive `AclLifecycleState` transitions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum AclLifecycleInput {
+    InitiateConnection,
+    AcceptConnection,
+    ConnectionEstablished,
+    AuthenticationRequested,
+    AuthenticationComplete,
+    EncryptionRequested,
+    EncryptionChanged,
+    RoleSwitchRequested,
+    RoleChangeComplete,
+    DisconnectRequested,
+    Disconnected,
+}
+
+/// The `StateMachine` for `AclLifecycleState`; see the type-level docs there for context.
+struct AclLifecycle;
+
+impl StateMachine for AclLifecycle {
+    type State = AclLifecycleState;
+    type Input = AclLifecycleInput;
+
+    fn transition(
+        state: &AclLifecycleState,
+        input: &AclLifecycleInput,
+    ) -> Option<AclLifecycleState> {
+        use AclLifecycleInput::*;
+        use AclLifecycleState::*;
+        match (state, input) {
+            (Closed, InitiateConnection) => Some(Initiating),
+            (Closed, AcceptConnection) => Some(Accepting),
+            (Initiating, ConnectionEstablished) => Some(Connected),
+            (Accepting, ConnectionEstablished) => Some(Connected),
+            (Connected, AuthenticationRequested) => Some(Authenticating),
+            (Authenticating, AuthenticationComplete) => Some(Connected),
+            (Connected, EncryptionRequested) => Some(Encrypting),
+            (Encrypting, EncryptionChanged) => Some(Connected),
+            (Connected, RoleSwitchRequested) => Some(RoleSwitching),
+            (RoleSwitching, RoleChangeComplete) => Some(Connected),
+            (Connected, DisconnectRequested)
+            | (Authenticating, DisconnectRequested)
+            | (Encrypting, DisconnectRequested)
+            | (RoleSwitching, DisconnectRequested)
+            | (Initiating, DisconnectRequested)
+            | (Accepting, DisconnectRequested) => Some(Disconnecting),
+            (Disconnecting, Disconnected)
+            | (Connected, Disconnected)
+            | (Authenticating, Disconnected)
+            | (Encrypting, Disconnected)
+            | (RoleSwitching, Disconnected)
+            | (Initiating, Disconnected)
+            | (Accepting, Disconnected) => Some(Closed),
+            _ => None,
+        }
+    }
+}
+
+/// One timestamped occurrence on a device's timeline; see `EventTimeline`.
+enum DeviceEvent {
+    ConnStart { transport: Transport, handle: ConnectionHandle, initiator: InitiatorType },
+    ConnEnd { transport: Transport, handle: ConnectionHandle, initiator: InitiatorType },
+    L2capConnReq { psm: Psm, cid: Cid },
+    L2capConnRsp { cid: Cid, success: bool },
+    NameReport { name: String },
+    AddressType { address_type: AddressType },
+}
+
+impl fmt::Display for DeviceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeviceEvent::ConnStart { transport, handle, initiator } => {
+                write!(f, "ACL connected [{}] handle={} {}", transport, handle, initiator)
+            }
+            DeviceEvent::ConnEnd { transport, handle, initiator } => {
+                write!(f, "ACL disconnected [{}] handle={} {}", transport, handle, initiator)
+            }
+            DeviceEvent::L2capConnReq { psm, cid } => {
+                write!(f, "L2CAP connection request psm={} cid={}", psm, cid)
+            }
+            DeviceEvent::L2capConnRsp { cid, success } => {
+                write!(
+                    f,
+                    "L2CAP connection response cid={} {}",
+                    cid,
+                    if *success { "success" } else { "failed" }
+                )
+            }
+            DeviceEvent::NameReport { name } => write!(f, "name reported: {}", name),
+            DeviceEvent::AddressType { address_type } => {
+                write!(f, "address type updated: {}", address_type)
+            }
+        }
+    }
+}
+
+/// A bounded ring buffer of a device's `DeviceEvent`s, so a reviewer can reconstruct the sequence
+/// of connect/name/L2CAP/disconnect events leading up to a failure without keeping unbounded
+/// history. Modeled on Fuchsia inspect's `BoundedListNode`: pushing past `capacity` drops the
+/// oldest entry.
+struct EventTimeline {
+    capacity: usize,
+    events: std::collections::VecDeque<(NaiveDateTime, DeviceEvent)>,
+}
+
+impl EventTimeline {
+    fn new(capacity: usize) -> Self {
+        EventTimeline { capacity, events: std::collections::VecDeque::with_capacity(capacity) }
+    }
+
+    fn push(&mut self, ts: NaiveDateTime, event: DeviceEvent) {
+        if self.events.len() >= self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back((ts, event));
+    }
+}
+
+/// Number of events retained per device's `EventTimeline` before the oldest are dropped.
+const DEVICE_TIMELINE_CAPACITY: usize = 20;
+
+/// Assigned numbers (Bluetooth SIG) for 16-bit service UUIDs used to classify a device by the
+/// services it advertises, the way SDP/UUID tables in classic Bluetooth tooling map these to
+/// human-readable profiles.
+const SERVICE_UUID_HID: u16 = 0x1812;
+const SERVICE_UUID_A2DP_SINK: u16 = 0x110B;
+const SERVICE_UUID_A2DP_SOURCE: u16 = 0x110A;
+const SERVICE_UUID_HFP_AG: u16 = 0x111F;
+const SERVICE_UUID_HFP_HF: u16 = 0x111E;
+
+/// A coarse classification of a device derived from its advertised service UUIDs and
+/// manufacturer data, used as a `sort_addresses` tiebreaker so profile-identifiable devices rank
+/// above ones we have no other information about.
+#[derive(Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Debug)]
+enum DeviceClass {
+    Unknown,
+    Beacon,
+    Audio,
+    Hid,
+}
+
+impl fmt::Display for DeviceClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            DeviceClass::Unknown => "Unknown",
+            DeviceClass::Beacon => "Beacon",
+            DeviceClass::Audio => "Audio",
+            DeviceClass::Hid => "HID",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// The GAP/EIR advertising data decoded for a device, beyond its name(s). Accumulates across
+/// every advertising report/EIR blob seen for the device, since fields may arrive split across
+/// several packets.
+#[derive(Default)]
+struct GapInformation {
+    flags: Option<u8>,
+    service_uuids_16: HashSet<u16>,
+    service_uuids_32: HashSet<u32>,
+    service_uuids_128: HashSet<u128>,
+    /// Keyed by 16-bit service UUID; latest value wins.
+    service_data: HashMap<u16, Vec<u8>>,
+    /// Keyed by company identifier; latest value wins.
+    manufacturer_data: HashMap<u16, Vec<u8>>,
+    tx_power_level: Option<i8>,
+    appearance: Option<u16>,
+}
+
+impl GapInformation {
+    /// Classifies the device from whichever service UUIDs it has advertised so far, falling back
+    /// to `Beacon` for any other device that has advertised manufacturer data.
+    fn device_class(&self) -> DeviceClass {
+        if self.service_uuids_16.contains(&SERVICE_UUID_HID) {
+            DeviceClass::Hid
+        } else if self.service_uuids_16.contains(&SERVICE_UUID_A2DP_SINK)
+            || self.service_uuids_16.contains(&SERVICE_UUID_A2DP_SOURCE)
+            || self.service_uuids_16.contains(&SERVICE_UUID_HFP_AG)
+            || self.service_uuids_16.contains(&SERVICE_UUID_HFP_HF)
+        {
+            DeviceClass::Audio
+        } else if !self.manufacturer_data.is_empty() {
+            DeviceClass::Beacon
+        } else {
+            DeviceClass::Unknown
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "flags": self.flags,
+            "service_uuids_16": self.service_uuids_16.iter().cloned().collect::<Vec<_>>(),
+            "service_uuids_32": self.service_uuids_32.iter().cloned().collect::<Vec<_>>(),
+            "service_uuids_128": self.service_uuids_128.iter().map(|u| u.to_string()).collect::<Vec<_>>(),
+            "service_data": self.service_data.iter().map(|(uuid, data)| json!({"uuid": uuid, "data": data})).collect::<Vec<_>>(),
+            "manufacturer_data": self.manufacturer_data.iter().map(|(company_id, data)| json!({"company_id": company_id, "data": data})).collect::<Vec<_>>(),
+            "tx_power_level": self.tx_power_level,
+            "appearance": self.appearance,
+        })
+    }
+}
+
+impl fmt::Display for GapInformation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(flags) = self.flags {
+            writeln!(f, "    Flags: {:#04x}", flags)?;
+        }
+        if !self.service_uuids_16.is_empty() {
+            writeln!(f, "    16-bit service UUIDs: {:04x?}", self.service_uuids_16)?;
+        }
+        if !self.service_uuids_32.is_empty() {
+            writeln!(f, "    32-bit service UUIDs: {:08x?}", self.service_uuids_32)?;
+        }
+        if !self.service_uuids_128.is_empty() {
+            writeln!(f, "    128-bit service UUIDs: {:032x?}", self.service_uuids_128)?;
+        }
+        for (uuid, data) in &self.service_data {
+            writeln!(f, "    Service data [{:04x}]: {:02x?}", uuid, data)?;
+        }
+        for (company_id, data) in &self.manufacturer_data {
+            writeln!(f, "    Manufacturer data [{:04x}]: {:02x?}", company_id, data)?;
+        }
+        if let Some(tx_power_level) = self.tx_power_level {
+            writeln!(f, "    TX power level: {} dBm", tx_power_level)?;
+        }
+        if let Some(appearance) = self.appearance {
+            writeln!(f, "    Appearance: {:#06x}", appearance)?;
+        }
+        Ok(())
+    }
+}
+
 /// Information about a specific device address
 struct DeviceInformation {
     names: HashSet<String>,
     address: Address,
     address_type: AddressType,
     acls: HashMap<Transport, Vec<AclInformation>>,
-    acl_state: HashMap<Transport, AclState>,
+    acl_state: HashMap<Transport, AclLifecycleState>,
+    /// Chronological trail of connect/name/L2CAP/disconnect events, for reconstructing the
+    /// sequence that led to a failure even after the aggregate state has moved on.
+    timeline: EventTimeline,
+    /// GAP/EIR advertising data decoded for this device, beyond its name(s).
+    gap: GapInformation,
 }
 
 impl DeviceInformation {
@@ -154,9 +493,40 @@ impl DeviceInformation {
             address_type: AddressType::None,
             acls: HashMap::from([(Transport::BREDR, vec![]), (Transport::LE, vec![])]),
             acl_state: HashMap::from([
-                (Transport::BREDR, AclState::None),
-                (Transport::LE, AclState::None),
+                (Transport::BREDR, AclLifecycleState::Closed),
+                (Transport::LE, AclLifecycleState::Closed),
             ]),
+            timeline: EventTimeline::new(DEVICE_TIMELINE_CAPACITY),
+            gap: GapInformation::default(),
+        }
+    }
+
+    fn device_class(&self) -> DeviceClass {
+        self.gap.device_class()
+    }
+
+    fn acl_state(&self, transport: Transport) -> AclLifecycleState {
+        *self.acl_state.get(&transport).unwrap_or(&AclLifecycleState::Closed)
+    }
+
+    /// Applies `input` to `transport`'s lifecycle state if it's a legal transition from the
+    /// current state, returning the new state. Otherwise leaves the state untouched and returns a
+    /// description of the unexpected input, for the caller to surface as a `Signal`.
+    fn apply_acl_transition(
+        &mut self,
+        transport: Transport,
+        input: AclLifecycleInput,
+    ) -> Result<AclLifecycleState, String> {
+        let current = self.acl_state(transport);
+        match AclLifecycle::transition(&current, &input) {
+            Some(next) => {
+                self.acl_state.insert(transport, next);
+                Ok(next)
+            }
+            None => Err(format!(
+                "{} [{}]: unexpected {:?} while in {:?} state",
+                self.address, transport, input, current
+            )),
         }
     }
 
@@ -188,43 +558,39 @@ impl DeviceInformation {
         handle: ConnectionHandle,
         transport: Transport,
         ts: NaiveDateTime,
-    ) {
+    ) -> Option<String> {
         if transport == Transport::Unknown {
-            return;
+            return None;
         }
 
         let mut acl = AclInformation::new(handle, transport);
-        let initiator = self.acl_state[&transport].get_connection_initiator();
+        let initiator = self.acl_state(transport).get_connection_initiator();
         acl.report_start(initiator, ts);
         self.acls.get_mut(&transport).unwrap().push(acl);
-        self.acl_state.insert(transport, AclState::Connected);
+        self.timeline.push(ts, DeviceEvent::ConnStart { transport, handle, initiator });
+        self.apply_acl_transition(transport, AclLifecycleInput::ConnectionEstablished).err()
     }
 
     fn report_connection_end(
         &mut self,
         handle: ConnectionHandle,
+        transport: Transport,
         initiator: InitiatorType,
         ts: NaiveDateTime,
-    ) {
-        for transport in [Transport::BREDR, Transport::LE] {
-            if self.is_connection_active(transport) {
-                if self.acls[&transport].last().unwrap().handle == handle {
-                    self.acls
-                        .get_mut(&transport)
-                        .unwrap()
-                        .last_mut()
-                        .unwrap()
-                        .report_end(initiator, ts);
-                    self.acl_state.insert(transport, AclState::None);
-                    return;
-                }
-            }
+    ) -> Option<String> {
+        if self.is_connection_active(transport)
+            && self.acls[&transport].last().unwrap().handle == handle
+        {
+            self.acls.get_mut(&transport).unwrap().last_mut().unwrap().report_end(initiator, ts);
+            self.timeline.push(ts, DeviceEvent::ConnEnd { transport, handle, initiator });
+            return self.apply_acl_transition(transport, AclLifecycleInput::Disconnected).err();
         }
 
         eprintln!(
             "device {} receive disconnection of handle {} without corresponding connection at {}",
             self.address, handle, ts
         );
+        None
     }
 
     fn print_names(names: &HashSet<String>) -> String {
@@ -234,33 +600,147 @@ impl DeviceInformation {
             names.iter().next().unwrap_or(&String::from("<Unknown name>")).to_owned()
         }
     }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "address": self.address.to_string(),
+            "address_type": self.address_type.to_string(),
+            "names": self.names.iter().cloned().collect::<Vec<_>>(),
+            "device_class": self.device_class().to_string(),
+            "gap": self.gap.to_json(),
+            "acls": {
+                "bredr": self.acls[&Transport::BREDR].iter().map(AclInformation::to_json).collect::<Vec<_>>(),
+                "le": self.acls[&Transport::LE].iter().map(AclInformation::to_json).collect::<Vec<_>>(),
+            },
+        })
+    }
 }
 
 impl fmt::Display for DeviceInformation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let _ = writeln!(
             f,
-            "{address} ({address_type}, {device_names}), {num_connections} connections",
+            "{address} ({address_type}, {device_names}, {device_class}), {num_connections} connections",
             address = self.address,
             address_type = self.address_type,
             device_names = DeviceInformation::print_names(&self.names),
+            device_class = self.device_class(),
             num_connections = self.acls.len()
         );
+        let _ = write!(f, "{}", self.gap);
         for acl in &self.acls[&Transport::BREDR] {
             let _ = write!(f, "{}", acl);
         }
         for acl in &self.acls[&Transport::LE] {
             let _ = write!(f, "{}", acl);
         }
+        if !self.timeline.events.is_empty() {
+            let _ = writeln!(f, "    Timeline (most recent {} events):", self.timeline.capacity);
+            for (ts, event) in &self.timeline.events {
+                let _ = writeln!(f, "      [{}] {}", ts.time(), event);
+            }
+        }
 
         Ok(())
     }
 }
 
+/// Credit-flow-control bookkeeping for one direction of an LE Credit Based (or Enhanced Credit
+/// Based) channel, i.e. the number of K-frames this side is still allowed to send before it must
+/// wait for the peer to return credits via an LE Flow Control Credit Indication.
+#[derive(Debug)]
+struct LeCreditState {
+    mtu: u16,
+    mps: u16,
+    credits: u32,
+    /// Lowest credit balance observed so far on this channel.
+    min_credits_seen: u32,
+    /// Number of times the balance dropped to zero while the channel stayed open.
+    times_hit_zero: u32,
+    /// `(ts credits hit zero, ts credits were returned)` for each zero-credit interval seen so
+    /// far; the last entry's end is `INVALID_TS` while a stall is still ongoing.
+    zero_credit_intervals: Vec<(NaiveDateTime, NaiveDateTime)>,
+}
+
+impl LeCreditState {
+    fn new(mtu: u16, mps: u16, credits: u32) -> Self {
+        LeCreditState {
+            mtu,
+            mps,
+            credits,
+            min_credits_seen: credits,
+            times_hit_zero: 0,
+            zero_credit_intervals: vec![],
+        }
+    }
+
+    /// A K-frame (first fragment of an SDU) was observed being sent on this channel; consumes
+    /// one credit.
+    fn consume_credit(&mut self, ts: NaiveDateTime) {
+        self.credits = self.credits.saturating_sub(1);
+        self.min_credits_seen = std::cmp::min(self.min_credits_seen, self.credits);
+        if self.credits == 0 {
+            self.times_hit_zero += 1;
+            self.zero_credit_intervals.push((ts, INVALID_TS));
+        }
+    }
+
+    /// An LE Flow Control Credit Indication was received for this channel; grants more credits.
+    fn add_credits(&mut self, credits: u16, ts: NaiveDateTime) {
+        if self.credits == 0 {
+            if let Some(last) = self.zero_credit_intervals.last_mut() {
+                if last.1 == INVALID_TS {
+                    last.1 = ts;
+                }
+            }
+        }
+        self.credits += credits as u32;
+    }
+
+    /// True if this channel ever stalled (reached zero credits while the connection stayed
+    /// open), regardless of whether it has since recovered.
+    fn has_stalled(&self) -> bool {
+        self.times_hit_zero > 0
+    }
+}
+
 #[derive(Debug)]
 enum CidState {
     Pending(Psm),
+    /// An LE Credit Based (or ECRED) connection request/response has been sent for this CID but
+    /// not yet answered. Carries the SPSM and the flow-control parameters offered by this side,
+    /// so they're available once the response arrives to build the connected `LeCreditState`.
+    LeCreditPending(Psm, u16, u16, u32),
     Connected(Cid, Psm),
+    LeConnected(Cid, Psm, LeCreditState),
+}
+
+impl CidState {
+    fn to_json(&self) -> Value {
+        match self {
+            CidState::Pending(psm) => json!({"state": "pending", "psm": psm}),
+            CidState::LeCreditPending(spsm, mtu, mps, initial_credits) => json!({
+                "state": "le_credit_pending",
+                "spsm": spsm,
+                "mtu": mtu,
+                "mps": mps,
+                "initial_credits": initial_credits,
+            }),
+            CidState::Connected(peer_cid, psm) => {
+                json!({"state": "connected", "peer_cid": peer_cid, "psm": psm})
+            }
+            CidState::LeConnected(peer_cid, spsm, credit_state) => json!({
+                "state": "le_connected",
+                "peer_cid": peer_cid,
+                "spsm": spsm,
+                "mtu": credit_state.mtu,
+                "mps": credit_state.mps,
+                "credits": credit_state.credits,
+                "min_credits_seen": credit_state.min_credits_seen,
+                "stalled": credit_state.has_stalled(),
+            }),
+        }
+    }
 }
 
 /// Information for an ACL connection session
@@ -275,6 +755,9 @@ struct AclInformation {
     inactive_profiles: Vec<ProfileInformation>,
     host_cids: HashMap<Cid, CidState>,
     peer_cids: HashMap<Cid, CidState>,
+    /// DLCIs for which an RFCOMM DISC has been sent but no UA has confirmed it yet, so the
+    /// matching ProfileInformation can be ended on the acknowledgement rather than the request.
+    rfcomm_pending_disc: HashSet<u8>,
 }
 
 impl AclInformation {
@@ -290,6 +773,7 @@ impl AclInformation {
             inactive_profiles: vec![],
             host_cids: HashMap::new(),
             peer_cids: HashMap::new(),
+            rfcomm_pending_disc: HashSet::new(),
         }
     }
 
@@ -442,6 +926,241 @@ impl AclInformation {
             self.report_profile_end(profile, profile_id, initiator, ts)
         }
     }
+
+    /// Handles an LE Credit Based (or Enhanced Credit Based, which opens several CIDs at once)
+    /// Connection Request. `cids` are the source CIDs the requester wants to open; `mtu`/`mps`/
+    /// `initial_credits` are shared across all of them and describe what the requester is
+    /// offering the peer for sending data back.
+    fn report_le_credit_conn_req(
+        &mut self,
+        spsm: Psm,
+        cids: &[Cid],
+        mtu: u16,
+        mps: u16,
+        initial_credits: u32,
+        initiator: InitiatorType,
+        _ts: NaiveDateTime,
+    ) {
+        for &cid in cids {
+            let pending = CidState::LeCreditPending(spsm, mtu, mps, initial_credits);
+            if initiator == InitiatorType::Host {
+                self.host_cids.insert(cid, pending);
+            } else if initiator == InitiatorType::Peer {
+                self.peer_cids.insert(cid, pending);
+            }
+        }
+    }
+
+    /// Handles an LE Credit Based (or ECRED) Connection Response. `cids` pairs each of the
+    /// requester's source CIDs with the destination CID the peer assigned it (CIDs that failed
+    /// to open in an ECRED batch come back as `0` and are skipped). `mtu`/`mps`/`initial_credits`
+    /// describe what the peer is granting the requester for sending data.
+    fn report_le_credit_conn_rsp(
+        &mut self,
+        result: LeCreditBasedConnectionResponseResult,
+        cids: &[CidInformation],
+        mtu: u16,
+        mps: u16,
+        initial_credits: u32,
+        initiator: InitiatorType,
+        ts: NaiveDateTime,
+    ) {
+        for &cid_info in cids {
+            if cid_info.peer_cid == 0 {
+                continue;
+            }
+
+            let host_cid = cid_info.host_cid;
+            let peer_cid = cid_info.peer_cid;
+            let cid_state_option = match initiator {
+                InitiatorType::Host => self.host_cids.get(&host_cid),
+                InitiatorType::Peer => self.peer_cids.get(&peer_cid),
+                _ => None,
+            };
+
+            let pending = match cid_state_option {
+                Some(CidState::LeCreditPending(spsm, req_mtu, req_mps, req_initial_credits)) => {
+                    Some((*spsm, *req_mtu, *req_mps, *req_initial_credits))
+                }
+                _ => None,
+            };
+
+            let (spsm, req_mtu, req_mps, req_initial_credits) = match pending {
+                Some(pending) => pending,
+                None => continue,
+            };
+
+            let profile_option = ProfileType::from_spsm(spsm);
+            let profile_id = ProfileId::L2capCid(cid_info);
+            if result == LeCreditBasedConnectionResponseResult::Success {
+                self.host_cids.insert(
+                    host_cid,
+                    CidState::LeConnected(
+                        peer_cid,
+                        spsm,
+                        LeCreditState::new(mtu, mps, initial_credits),
+                    ),
+                );
+                self.peer_cids.insert(
+                    peer_cid,
+                    CidState::LeConnected(
+                        host_cid,
+                        spsm,
+                        LeCreditState::new(req_mtu, req_mps, req_initial_credits),
+                    ),
+                );
+                if let Some(profile) = profile_option {
+                    self.report_profile_start(profile, profile_id, initiator, ts);
+                    if let Some(profile) = self.active_profiles.get_mut(&profile_id) {
+                        profile.le_coc_bytes = Some(0);
+                    }
+                }
+            } else {
+                // On failure, report start and end on the same time.
+                if let Some(profile) = profile_option {
+                    self.report_profile_start(profile, profile_id, initiator, ts);
+                    self.report_profile_end(profile, profile_id, initiator, ts);
+                }
+            }
+        }
+    }
+
+    /// An LE Flow Control Credit Indication was observed for `cid`; grants `credits` more credits
+    /// to whichever side's channel it belongs to (the sender of the indication is replenishing
+    /// the *other* side's ability to send).
+    fn report_le_flow_control_credit(&mut self, cid: Cid, credits: u16, ts: NaiveDateTime) {
+        if let Some((_, credit_state)) = self.find_le_connected_cid_mut(cid) {
+            credit_state.add_credits(credits, ts);
+        }
+    }
+
+    /// A K-frame (first fragment of an SDU) carrying `payload_len` bytes was observed on `cid`;
+    /// consumes one credit and adds to the channel's running byte total. A CID is only locally
+    /// significant to whichever side owns it, so (as with `report_le_flow_control_credit`) the
+    /// CID value alone identifies the right entry.
+    fn report_le_kframe(&mut self, cid: Cid, payload_len: usize, ts: NaiveDateTime) {
+        let (profile_id, stalled) = match self.find_le_connected_cid_mut(cid) {
+            Some((profile_id, credit_state)) => {
+                credit_state.consume_credit(ts);
+                (profile_id, credit_state.has_stalled())
+            }
+            None => return,
+        };
+        if let Some(profile) = self.active_profiles.get_mut(&profile_id) {
+            profile.stalled = stalled;
+            if let Some(bytes) = profile.le_coc_bytes.as_mut() {
+                *bytes += payload_len as u64;
+            }
+        }
+    }
+
+    /// Finds the `CidState::LeConnected` entry keyed by `cid` in either `host_cids` or
+    /// `peer_cids`, returning the `ProfileId` it's tracked under alongside its credit state.
+    fn find_le_connected_cid_mut(&mut self, cid: Cid) -> Option<(ProfileId, &mut LeCreditState)> {
+        if let Some(CidState::LeConnected(peer_cid, _, credit_state)) = self.host_cids.get_mut(&cid)
+        {
+            let profile_id =
+                ProfileId::L2capCid(CidInformation { host_cid: cid, peer_cid: *peer_cid });
+            return Some((profile_id, credit_state));
+        }
+        if let Some(CidState::LeConnected(host_cid, _, credit_state)) = self.peer_cids.get_mut(&cid)
+        {
+            let profile_id =
+                ProfileId::L2capCid(CidInformation { host_cid: *host_cid, peer_cid: cid });
+            return Some((profile_id, credit_state));
+        }
+        None
+    }
+
+    /// Finds the base (PSM-3) L2CAP CID pair that `cid` (whichever side's representation it is)
+    /// belongs to, if it's a classic RFCOMM connection.
+    fn rfcomm_base_cid_info(&self, cid: Cid) -> Option<CidInformation> {
+        if let Some(CidState::Connected(peer_cid, psm)) = self.host_cids.get(&cid) {
+            if *psm == RFCOMM_PSM {
+                return Some(CidInformation { host_cid: cid, peer_cid: *peer_cid });
+            }
+        }
+        if let Some(CidState::Connected(host_cid, psm)) = self.peer_cids.get(&cid) {
+            if *psm == RFCOMM_PSM {
+                return Some(CidInformation { host_cid: *host_cid, peer_cid: cid });
+            }
+        }
+        None
+    }
+
+    /// Parses one RFCOMM frame multiplexed over `cid` (a connected RFCOMM PSM-3 L2CAP channel)
+    /// and, for SABM/DISC/UA control frames, starts or ends a distinct `ProfileInformation` for
+    /// that DLCI so multiple logical serial channels on the same link are tracked separately.
+    fn report_rfcomm_frame(
+        &mut self,
+        cid: Cid,
+        payload: &[u8],
+        initiator: InitiatorType,
+        ts: NaiveDateTime,
+    ) {
+        let base_cid = match self.rfcomm_base_cid_info(cid) {
+            Some(base_cid) => base_cid,
+            None => return,
+        };
+        let (dlci, control) = match parse_rfcomm_address_and_control(payload) {
+            Some(parsed) => parsed,
+            None => return,
+        };
+        if dlci == 0 {
+            // DLCI 0 is the RFCOMM multiplexer control channel itself, not a logical serial
+            // channel worth reporting.
+            return;
+        }
+
+        let profile_id = ProfileId::RfcommDlci { cid: base_cid, dlci };
+        match control {
+            RFCOMM_CONTROL_SABM => {
+                self.report_profile_start(ProfileType::Rfcomm, profile_id, initiator, ts);
+            }
+            RFCOMM_CONTROL_DISC => {
+                self.rfcomm_pending_disc.insert(dlci);
+            }
+            RFCOMM_CONTROL_UA => {
+                if self.rfcomm_pending_disc.remove(&dlci) {
+                    self.report_profile_end(ProfileType::Rfcomm, profile_id, initiator, ts);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Every CID entry in `cids`, as a JSON array, with each CID's own state merged in alongside
+    /// its `cid` value.
+    fn cids_to_json(cids: &HashMap<Cid, CidState>) -> Value {
+        Value::Array(
+            cids.iter()
+                .map(|(cid, state)| {
+                    let mut entry = state.to_json();
+                    entry.as_object_mut().unwrap().insert("cid".to_string(), json!(cid));
+                    entry
+                })
+                .collect(),
+        )
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "handle": self.handle,
+            "transport": self.transport.to_string(),
+            "start_time": ts_to_json(self.start_time),
+            "end_time": ts_to_json(self.end_time),
+            "start_initiator": self.start_initiator.to_string(),
+            "end_initiator": self.end_initiator.to_string(),
+            "profiles": self
+                .active_profiles
+                .values()
+                .chain(self.inactive_profiles.iter())
+                .map(ProfileInformation::to_json)
+                .collect::<Vec<_>>(),
+            "host_cids": Self::cids_to_json(&self.host_cids),
+            "peer_cids": Self::cids_to_json(&self.peer_cids),
+        })
+    }
 }
 
 impl fmt::Display for AclInformation {
@@ -481,6 +1200,11 @@ enum ProfileType {
     HidIntr,
     Rfcomm,
     Sdp,
+    // LE Credit Based / ECRED channels, identified by SPSM rather than PSM.
+    GattOverCoc,
+    MeshProvisioning,
+    MeshProxy,
+    Ots,
 }
 
 impl fmt::Display for ProfileType {
@@ -495,6 +1219,10 @@ impl fmt::Display for ProfileType {
             ProfileType::HidIntr => "HID INTR",
             ProfileType::Rfcomm => "RFCOMM",
             ProfileType::Sdp => "SDP",
+            ProfileType::GattOverCoc => "GATT-over-CoC",
+            ProfileType::MeshProvisioning => "Mesh Provisioning",
+            ProfileType::MeshProxy => "Mesh Proxy",
+            ProfileType::Ots => "OTS",
         };
         write!(f, "{}", str)
     }
@@ -504,7 +1232,7 @@ impl ProfileType {
     fn from_psm(psm: Psm) -> Option<Self> {
         match psm {
             1 => Some(ProfileType::Sdp),
-            3 => Some(ProfileType::Rfcomm),
+            RFCOMM_PSM => Some(ProfileType::Rfcomm),
             17 => Some(ProfileType::HidCtrl),
             19 => Some(ProfileType::HidIntr),
             23 => Some(ProfileType::Avctp),
@@ -514,6 +1242,19 @@ impl ProfileType {
             _ => None,
         }
     }
+
+    /// Maps a well-known LE PSM (SPSM) used by an LE Credit Based / ECRED channel to a profile,
+    /// analogous to `from_psm` for classic fixed-PSM L2CAP channels.
+    fn from_spsm(spsm: Psm) -> Option<Self> {
+        match spsm {
+            0x0025 => Some(ProfileType::Ots),
+            0x0027 => Some(ProfileType::Eatt),
+            0x0029 => Some(ProfileType::GattOverCoc),
+            0x0827 => Some(ProfileType::MeshProvisioning),
+            0x0828 => Some(ProfileType::MeshProxy),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Eq, Hash, PartialEq)]
@@ -523,12 +1264,14 @@ struct CidInformation {
 }
 
 // Use to distinguish between the same profiles within one ACL connection.
-// Later we can add RFCOMM's DLCI, for example.
 // This is used as the key of the map of active profiles in AclInformation.
 #[derive(Clone, Copy, Eq, Hash, PartialEq)]
 enum ProfileId {
     OnePerConnection(ProfileType),
     L2capCid(CidInformation),
+    /// One RFCOMM logical serial channel (e.g. one of several DLCIs multiplexed over a single
+    /// PSM-3 L2CAP connection, such as HFP and a SPP session sharing one link).
+    RfcommDlci { cid: CidInformation, dlci: u8 },
 }
 
 impl fmt::Display for ProfileId {
@@ -538,6 +1281,75 @@ impl fmt::Display for ProfileId {
             ProfileId::L2capCid(cid_info) => {
                 format!("(CID: host={}, peer={})", cid_info.host_cid, cid_info.peer_cid)
             }
+            ProfileId::RfcommDlci { cid, dlci } => {
+                format!(
+                    "(CID: host={}, peer={}, DLCI: {})",
+                    cid.host_cid, cid.peer_cid, dlci
+                )
+            }
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl ProfileId {
+    fn to_json(&self) -> Value {
+        match self {
+            ProfileId::OnePerConnection(profile_type) => {
+                json!({"kind": "one_per_connection", "profile_type": profile_type.to_string()})
+            }
+            ProfileId::L2capCid(cid_info) => json!({
+                "kind": "l2cap_cid",
+                "host_cid": cid_info.host_cid,
+                "peer_cid": cid_info.peer_cid,
+            }),
+            ProfileId::RfcommDlci { cid, dlci } => json!({
+                "kind": "rfcomm_dlci",
+                "host_cid": cid.host_cid,
+                "peer_cid": cid.peer_cid,
+                "dlci": dlci,
+            }),
+        }
+    }
+}
+
+/// Whether a synchronous (audio) link negotiated as a basic SCO or an enhanced eSCO connection.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum ScoLinkType {
+    Sco,
+    Esco,
+}
+
+impl fmt::Display for ScoLinkType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            ScoLinkType::Sco => "SCO",
+            ScoLinkType::Esco => "eSCO",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// The air-mode codec negotiated for a SCO/eSCO link, decoded from `SynchronousConnectionComplete`'s
+/// air mode field. Transparent air mode carries a codec (e.g.
+/// mSBC) negotiated out-of-band over HFP AT commands, so it's reported as such rather than as an
+/// unknown codec.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum ScoCodec {
+    Cvsd,
+    /// Transparent air mode; in practice this always carries mSBC wideband speech over HFP.
+    Transparent,
+    ULaw,
+    ALaw,
+}
+
+impl fmt::Display for ScoCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let str = match self {
+            ScoCodec::Cvsd => "CVSD",
+            ScoCodec::Transparent => "Transparent (mSBC)",
+            ScoCodec::ULaw => "u-law",
+            ScoCodec::ALaw => "A-law",
         };
         write!(f, "{}", str)
     }
@@ -550,6 +1362,15 @@ struct ProfileInformation {
     start_initiator: InitiatorType,
     end_initiator: InitiatorType,
     profile_id: ProfileId,
+    /// Set once an LE Credit Based channel backing this profile reaches zero credits while the
+    /// connection stays open; see `AclInformation::report_le_kframe`.
+    stalled: bool,
+    /// Running total of K-frame payload bytes seen on this channel, if it's an LE Credit Based
+    /// connection; `None` for profiles that aren't LE CoC channels (RFCOMM DLCIs, classic L2CAP).
+    le_coc_bytes: Option<u64>,
+    /// Link type and negotiated codec, if this profile is the SCO/eSCO link underlying an HFP
+    /// session; `None` for every other profile type.
+    sco_link: Option<(ScoLinkType, ScoCodec)>,
 }
 
 impl ProfileInformation {
@@ -561,6 +1382,9 @@ impl ProfileInformation {
             start_initiator: InitiatorType::Unknown,
             end_initiator: InitiatorType::Unknown,
             profile_id: profile_id,
+            stalled: false,
+            le_coc_bytes: None,
+            sco_link: None,
         }
     }
 
@@ -573,13 +1397,28 @@ impl ProfileInformation {
         self.end_initiator = initiator;
         self.end_time = ts;
     }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "profile_type": self.profile_type.to_string(),
+            "profile_id": self.profile_id.to_json(),
+            "start_time": ts_to_json(self.start_time),
+            "end_time": ts_to_json(self.end_time),
+            "start_initiator": self.start_initiator.to_string(),
+            "end_initiator": self.end_initiator.to_string(),
+            "stalled": self.stalled,
+            "le_coc_bytes": self.le_coc_bytes,
+            "sco_link_type": self.sco_link.map(|(link_type, _)| link_type.to_string()),
+            "sco_codec": self.sco_link.map(|(_, codec)| codec.to_string()),
+        })
+    }
 }
 
 impl fmt::Display for ProfileInformation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(
             f,
-            "    {profile}, {timestamp_initiator_info} {profile_id}",
+            "    {profile}, {timestamp_initiator_info} {profile_id}{stalled}{sco_link}",
             profile = self.profile_type,
             timestamp_initiator_info = print_timestamps_and_initiator(
                 self.start_time,
@@ -588,15 +1427,111 @@ impl fmt::Display for ProfileInformation {
                 self.end_initiator
             ),
             profile_id = self.profile_id,
+            stalled = match (self.le_coc_bytes, self.stalled) {
+                (Some(bytes), true) => format!(" [{} bytes, STALLED: ran out of CoC credits]", bytes),
+                (Some(bytes), false) => format!(" [{} bytes]", bytes),
+                (None, _) => "".to_string(),
+            },
+            sco_link = match self.sco_link {
+                Some((link_type, codec)) => format!(" [{}, {}]", link_type, codec),
+                None => "".to_string(),
+            },
         )
     }
 }
 
+/// One identifier that currently points at an active connection: an ACL handle, a SCO handle
+/// riding on top of one, or a locally- ("host") or peer-assigned L2CAP CID on a given transport.
+/// Modeled after a socketmap: every identifier a packet can carry resolves through this one type.
+///
+/// This tracks identity only - *which* connection a handle/CID currently belongs to. The richer
+/// per-CID state (pending/connected, PSM, LE credit accounting, ...) still lives in
+/// `AclInformation::host_cids`/`peer_cids`; this index exists so that resolving any of those
+/// identifiers, in either direction, is O(1) instead of a linear scan, and so a handle or CID
+/// getting reused for a different connection before the old one was torn down shows up as a
+/// collision instead of silently overwriting stale state.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum ConnectionKey {
+    AclHandle(ConnectionHandle),
+    ScoHandle(ConnectionHandle),
+    HostCid(Transport, Cid),
+    PeerCid(Transport, Cid),
+}
+
+/// A bidirectional index from `ConnectionKey` to the `(Address, Transport)` connection it
+/// currently identifies.
+#[derive(Default)]
+struct ConnectionIndex {
+    forward: HashMap<ConnectionKey, (Address, Transport)>,
+    reverse: HashMap<(Address, Transport), HashSet<ConnectionKey>>,
+}
+
+impl ConnectionIndex {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `key` as identifying `address`/`transport`. If `key` is already registered to a
+    /// *different* connection, the existing registration is left untouched and that connection is
+    /// returned as an error, so the caller can report the collision instead of silently
+    /// overwriting it.
+    fn insert(
+        &mut self,
+        key: ConnectionKey,
+        address: Address,
+        transport: Transport,
+    ) -> Result<(), (Address, Transport)> {
+        if let Some(existing) = self.forward.get(&key) {
+            return if *existing == (address, transport) { Ok(()) } else { Err(*existing) };
+        }
+        self.forward.insert(key, (address, transport));
+        self.reverse.entry((address, transport)).or_insert_with(HashSet::new).insert(key);
+        Ok(())
+    }
+
+    fn get(&self, key: &ConnectionKey) -> Option<(Address, Transport)> {
+        self.forward.get(key).copied()
+    }
+
+    fn remove(&mut self, key: &ConnectionKey) {
+        if let Some(owner) = self.forward.remove(key) {
+            if let Some(keys) = self.reverse.get_mut(&owner) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.reverse.remove(&owner);
+                }
+            }
+        }
+    }
+
+    /// Removes every key currently registered to `address`/`transport` (e.g. the ACL handle, any
+    /// SCO handle riding on it, and every L2CAP CID opened over it), returning them.
+    fn remove_all(&mut self, address: Address, transport: Transport) -> Vec<ConnectionKey> {
+        match self.reverse.remove(&(address, transport)) {
+            Some(keys) => {
+                for key in &keys {
+                    self.forward.remove(key);
+                }
+                keys.into_iter().collect()
+            }
+            None => vec![],
+        }
+    }
+
+    /// All currently-registered ACL handles, e.g. to walk on a full reset.
+    fn acl_handles(&self) -> impl Iterator<Item = ConnectionHandle> + '_ {
+        self.forward.keys().filter_map(|key| match key {
+            ConnectionKey::AclHandle(handle) => Some(*handle),
+            _ => None,
+        })
+    }
+}
+
 /// This rule prints devices names and connection/disconnection time.
 struct InformationalRule {
     devices: HashMap<Address, DeviceInformation>,
-    handles: HashMap<ConnectionHandle, Address>,
-    sco_handles: HashMap<ConnectionHandle, ConnectionHandle>,
+    /// Resolves ACL/SCO handles and L2CAP CIDs to the connection they currently belong to.
+    index: ConnectionIndex,
     /// unknownConnections store connections which is initiated before btsnoop starts.
     unknown_connections: HashMap<ConnectionHandle, AclInformation>,
     /// Store the pending disconnection so we can retrieve who initiates it upon report.
@@ -606,16 +1541,19 @@ struct InformationalRule {
     /// Also, when powering off, the controller might or might not reply the disconnection request.
     /// Therefore also store this information so we can correctly handle both scenario.
     pending_disconnections: HashMap<ConnectionHandle, bool>, // is powering off?
+    /// Signals raised for HCI commands/events that don't make sense given the ACL lifecycle state
+    /// the connection is currently tracked as being in (see `AclLifecycle`).
+    signals: Vec<Signal>,
 }
 
 impl InformationalRule {
     pub fn new() -> Self {
         InformationalRule {
             devices: HashMap::new(),
-            handles: HashMap::new(),
-            sco_handles: HashMap::new(),
+            index: ConnectionIndex::new(),
             unknown_connections: HashMap::new(),
             pending_disconnections: HashMap::new(),
+            signals: vec![],
         }
     }
 
@@ -642,29 +1580,87 @@ impl InformationalRule {
         handle: ConnectionHandle,
         transport: Transport,
     ) -> &mut AclInformation {
-        if !self.handles.contains_key(&handle) || transport == Transport::Unknown {
-            let conn = self.get_or_allocate_unknown_connection(handle, transport);
-            return conn;
-        }
+        let owner = if transport == Transport::Unknown {
+            None
+        } else {
+            self.index.get(&ConnectionKey::AclHandle(handle))
+        };
 
-        let address = &self.handles.get(&handle).unwrap().clone();
-        let device = self.get_or_allocate_device(address);
-        return device.get_or_allocate_connection(handle, transport);
+        match owner {
+            None => self.get_or_allocate_unknown_connection(handle, transport),
+            Some((address, _)) => {
+                let device = self.get_or_allocate_device(&address);
+                device.get_or_allocate_connection(handle, transport)
+            }
+        }
     }
 
-    fn report_address_type(&mut self, address: &Address, address_type: AddressType) {
+    fn report_address_type(
+        &mut self,
+        address: &Address,
+        address_type: AddressType,
+        ts: NaiveDateTime,
+    ) {
         let device = self.get_or_allocate_device(address);
         device.address_type.update(address_type);
+        device.timeline.push(ts, DeviceEvent::AddressType { address_type });
     }
 
-    fn report_name(&mut self, address: &Address, name: &String) {
+    fn report_name(&mut self, address: &Address, name: &String, ts: NaiveDateTime) {
         let device = self.get_or_allocate_device(address);
         device.names.insert(name.into());
+        device.timeline.push(ts, DeviceEvent::NameReport { name: name.clone() });
+    }
+
+    /// Records `event` on `address`'s timeline, allocating the device if it isn't known yet.
+    fn record_device_event(&mut self, address: &Address, event: DeviceEvent, ts: NaiveDateTime) {
+        let device = self.get_or_allocate_device(address);
+        device.timeline.push(ts, event);
     }
 
-    fn report_acl_state(&mut self, address: &Address, transport: Transport, state: AclState) {
+    /// Same as `record_device_event`, but resolves `address` from an ACL handle. A no-op if the
+    /// handle isn't tracked as an active connection.
+    fn record_device_event_for_handle(
+        &mut self,
+        handle: ConnectionHandle,
+        event: DeviceEvent,
+        ts: NaiveDateTime,
+    ) {
+        if let Some((address, _)) = self.index.get(&ConnectionKey::AclHandle(handle)) {
+            self.record_device_event(&address, event, ts);
+        }
+    }
+
+    /// Applies `input` to `address`'s `transport` lifecycle state, raising a `Signal` if it isn't
+    /// a legal transition from the state the connection is currently tracked as being in.
+    fn report_acl_lifecycle_event(
+        &mut self,
+        address: &Address,
+        transport: Transport,
+        input: AclLifecycleInput,
+        ts: NaiveDateTime,
+    ) {
         let device = self.get_or_allocate_device(address);
-        device.acl_state.insert(transport, state);
+        if let Err(data) = device.apply_acl_transition(transport, input) {
+            self.signals.push(Signal::new(ts, "acl_lifecycle".to_owned(), data));
+        }
+    }
+
+    /// Same as `report_acl_lifecycle_event`, but resolves the address/transport from a connection
+    /// handle. Silently a no-op if the handle isn't tracked as an active connection - the command
+    /// or event just doesn't apply to anything this rule is following.
+    fn report_acl_lifecycle_event_for_handle(
+        &mut self,
+        handle: ConnectionHandle,
+        input: AclLifecycleInput,
+        ts: NaiveDateTime,
+    ) {
+        if let Some((address, transport)) = self.index.get(&ConnectionKey::AclHandle(handle)) {
+            let device = self.devices.get_mut(&address).unwrap();
+            if let Err(data) = device.apply_acl_transition(transport, input) {
+                self.signals.push(Signal::new(ts, "acl_lifecycle".to_owned(), data));
+            }
+        }
     }
 
     fn report_connection_start(
@@ -675,15 +1671,27 @@ impl InformationalRule {
         ts: NaiveDateTime,
     ) {
         let device = self.get_or_allocate_device(address);
-        device.report_connection_start(handle, transport, ts);
-        self.handles.insert(handle, *address);
+        let signal_data = device.report_connection_start(handle, transport, ts);
+        if let Err((existing_address, existing_transport)) =
+            self.index.insert(ConnectionKey::AclHandle(handle), *address, transport)
+        {
+            eprintln!(
+                "handle {} reused: was {} [{}], now {} [{}]",
+                handle, existing_address, existing_transport, address, transport
+            );
+        }
         self.pending_disconnections.remove(&handle);
+        if let Some(data) = signal_data {
+            self.signals.push(Signal::new(ts, "acl_lifecycle".to_owned(), data));
+        }
     }
 
     fn report_sco_connection_start(
         &mut self,
         address: &Address,
         handle: ConnectionHandle,
+        link_type: ScoLinkType,
+        codec: ScoCodec,
         ts: NaiveDateTime,
     ) {
         if !self.devices.contains_key(address) {
@@ -700,17 +1708,22 @@ impl InformationalRule {
 
         // Whatever handle value works here - we aren't allocating a new one.
         let acl = device.get_or_allocate_connection(0, Transport::BREDR);
-        let acl_handle = acl.handle;
         // We need to listen the HCI commands to determine the correct initiator.
         // Here we just assume host for simplicity.
-        acl.report_profile_start(
-            ProfileType::Hfp,
-            ProfileId::OnePerConnection(ProfileType::Hfp),
-            InitiatorType::Host,
-            ts,
-        );
+        let profile_id = ProfileId::OnePerConnection(ProfileType::Hfp);
+        acl.report_profile_start(ProfileType::Hfp, profile_id, InitiatorType::Host, ts);
+        if let Some(profile) = acl.active_profiles.get_mut(&profile_id) {
+            profile.sco_link = Some((link_type, codec));
+        }
 
-        self.sco_handles.insert(handle, acl_handle);
+        if let Err((existing_address, existing_transport)) =
+            self.index.insert(ConnectionKey::ScoHandle(handle), *address, Transport::BREDR)
+        {
+            eprintln!(
+                "SCO handle {} reused: was {} [{}], now {} [{}]",
+                handle, existing_address, existing_transport, address, Transport::BREDR
+            );
+        }
     }
 
     fn report_connection_end(&mut self, handle: ConnectionHandle, ts: NaiveDateTime) {
@@ -720,9 +1733,9 @@ impl InformationalRule {
         };
 
         // This might be a SCO disconnection event, so check that first
-        if self.sco_handles.contains_key(&handle) {
-            let acl_handle = self.sco_handles[&handle];
-            let conn = self.get_or_allocate_connection(acl_handle, Transport::BREDR);
+        if let Some((address, transport)) = self.index.get(&ConnectionKey::ScoHandle(handle)) {
+            let device = self.devices.get_mut(&address).unwrap();
+            let conn = device.get_or_allocate_connection(0, transport);
             // in case of HFP failure, the initiator here would be set to peer, which is incorrect,
             // but when printing we detect by the timestamp that it was a failure anyway.
             conn.report_profile_end(
@@ -731,18 +1744,22 @@ impl InformationalRule {
                 initiator,
                 ts,
             );
+            self.index.remove(&ConnectionKey::ScoHandle(handle));
             return;
         }
 
         // Not recognized as SCO, assume it's an ACL handle.
-        if let Some(address) = self.handles.get(&handle) {
+        if let Some((address, transport)) = self.index.get(&ConnectionKey::AclHandle(handle)) {
             // This device is known
-            let device: &mut DeviceInformation = self.devices.get_mut(address).unwrap();
-            device.report_connection_end(handle, initiator, ts);
-            self.handles.remove(&handle);
+            let device: &mut DeviceInformation = self.devices.get_mut(&address).unwrap();
+            let signal_data = device.report_connection_end(handle, transport, initiator, ts);
+
+            // Drop the ACL handle itself, along with any SCO handle and L2CAP CIDs it carried.
+            self.index.remove_all(address, transport);
 
-            // remove the associated SCO handle, if any
-            self.sco_handles.retain(|_sco_handle, acl_handle| *acl_handle != handle);
+            if let Some(data) = signal_data {
+                self.signals.push(Signal::new(ts, "acl_lifecycle".to_owned(), data));
+            }
         } else {
             // Unknown device.
             let conn = self.get_or_allocate_unknown_connection(handle, Transport::Unknown);
@@ -751,32 +1768,53 @@ impl InformationalRule {
     }
 
     fn report_reset(&mut self, ts: NaiveDateTime) {
-        // report_connection_end removes the entries from the map, so store all the keys first.
-        let handles: Vec<ConnectionHandle> = self.handles.keys().cloned().collect();
+        // report_connection_end removes the entries from the index, so collect handles first.
+        let handles: Vec<ConnectionHandle> = self.index.acl_handles().collect();
         for handle in handles {
             self.report_connection_end(handle, ts);
         }
-        self.sco_handles.clear();
         self.pending_disconnections.clear();
     }
 
-    fn process_gap_data(&mut self, address: &Address, data: &GapData) {
+    fn process_gap_data(&mut self, address: &Address, data: &GapData, ts: NaiveDateTime) {
         match data.data_type {
             GapDataType::CompleteLocalName | GapDataType::ShortenedLocalName => {
                 let name = String::from_utf8_lossy(data.data.as_slice()).into_owned();
-                self.report_name(address, &name);
+                self.report_name(address, &name, ts);
+                return;
             }
 
             _ => {}
         }
+
+        if let Some(flags) = data.as_flags() {
+            self.get_or_allocate_device(address).gap.flags = Some(flags);
+        } else if let Some(uuids) = data.as_service_uuids_16() {
+            self.get_or_allocate_device(address).gap.service_uuids_16.extend(uuids);
+        } else if let Some(uuids) = data.as_service_uuids_32() {
+            self.get_or_allocate_device(address).gap.service_uuids_32.extend(uuids);
+        } else if let Some(uuids) = data.as_service_uuids_128() {
+            self.get_or_allocate_device(address).gap.service_uuids_128.extend(uuids);
+        } else if let Some((uuid, service_data)) = data.as_service_data_16() {
+            self.get_or_allocate_device(address).gap.service_data.insert(uuid, service_data);
+        } else if let Some((company_id, manufacturer_data)) = data.as_manufacturer_specific_data() {
+            self.get_or_allocate_device(address)
+                .gap
+                .manufacturer_data
+                .insert(company_id, manufacturer_data);
+        } else if let Some(tx_power_level) = data.as_tx_power_level() {
+            self.get_or_allocate_device(address).gap.tx_power_level = Some(tx_power_level);
+        } else if let Some(appearance) = data.as_appearance() {
+            self.get_or_allocate_device(address).gap.appearance = Some(appearance);
+        }
     }
 
-    fn process_raw_gap_data(&mut self, address: &Address, data: &[u8]) {
+    fn process_raw_gap_data(&mut self, address: &Address, data: &[u8], ts: NaiveDateTime) {
         let mut offset = 0;
         while offset < data.len() {
             match GapData::parse(&data[offset..]) {
                 Ok(gap_data) => {
-                    self.process_gap_data(&address, &gap_data);
+                    self.process_gap_data(&address, &gap_data, ts);
                     // advance data len + 2 (size = 1, type = 1)
                     offset += gap_data.data.len() + 2;
                 }
@@ -791,6 +1829,21 @@ impl InformationalRule {
         }
     }
 
+    /// Registers an L2CAP CID (`ConnectionKey::HostCid`/`PeerCid`) as belonging to `address`,
+    /// logging (instead of overwriting) if it collides with a still-open different connection -
+    /// the same policy `report_connection_start`/`report_sco_connection_start` apply to ACL/SCO
+    /// handles.
+    fn register_l2cap_cid(&mut self, key: ConnectionKey, address: Address, transport: Transport) {
+        if let Err((existing_address, existing_transport)) =
+            self.index.insert(key, address, transport)
+        {
+            eprintln!(
+                "L2CAP CID {:?} reused: was {} [{}], now {} [{}]",
+                key, existing_address, existing_transport, address, transport
+            );
+        }
+    }
+
     fn report_l2cap_conn_req(
         &mut self,
         handle: ConnectionHandle,
@@ -801,6 +1854,23 @@ impl InformationalRule {
     ) {
         let conn = self.get_or_allocate_connection(handle, Transport::BREDR);
         conn.report_l2cap_conn_req(psm, cid, initiator, ts);
+        self.record_device_event_for_handle(handle, DeviceEvent::L2capConnReq { psm, cid }, ts);
+
+        if let Some((address, transport)) = self.index.get(&ConnectionKey::AclHandle(handle)) {
+            match initiator {
+                InitiatorType::Host => self.register_l2cap_cid(
+                    ConnectionKey::HostCid(transport, cid),
+                    address,
+                    transport,
+                ),
+                InitiatorType::Peer => self.register_l2cap_cid(
+                    ConnectionKey::PeerCid(transport, cid),
+                    address,
+                    transport,
+                ),
+                InitiatorType::Unknown => {}
+            }
+        }
     }
 
     fn report_l2cap_conn_rsp(
@@ -818,6 +1888,29 @@ impl InformationalRule {
         let conn = self.get_or_allocate_connection(handle, Transport::BREDR);
         let cid_info = CidInformation { host_cid, peer_cid };
         conn.report_l2cap_conn_rsp(status, cid_info, initiator, ts);
+        self.record_device_event_for_handle(
+            handle,
+            DeviceEvent::L2capConnRsp {
+                cid: host_cid,
+                success: status == ConnectionResponseResult::Success,
+            },
+            ts,
+        );
+
+        if status == ConnectionResponseResult::Success {
+            if let Some((address, transport)) = self.index.get(&ConnectionKey::AclHandle(handle)) {
+                self.register_l2cap_cid(
+                    ConnectionKey::HostCid(transport, host_cid),
+                    address,
+                    transport,
+                );
+                self.register_l2cap_cid(
+                    ConnectionKey::PeerCid(transport, peer_cid),
+                    address,
+                    transport,
+                );
+            }
+        }
     }
 
     fn report_l2cap_disconn_rsp(
@@ -831,6 +1924,97 @@ impl InformationalRule {
         let conn = self.get_or_allocate_connection(handle, Transport::BREDR);
         let cid_info = CidInformation { host_cid, peer_cid };
         conn.report_l2cap_disconn_rsp(cid_info, initiator, ts);
+        self.index.remove(&ConnectionKey::HostCid(Transport::BREDR, host_cid));
+        self.index.remove(&ConnectionKey::PeerCid(Transport::BREDR, peer_cid));
+    }
+
+    fn report_le_credit_conn_req(
+        &mut self,
+        handle: ConnectionHandle,
+        spsm: Psm,
+        cids: &[Cid],
+        mtu: u16,
+        mps: u16,
+        initial_credits: u32,
+        initiator: InitiatorType,
+        ts: NaiveDateTime,
+    ) {
+        let conn = self.get_or_allocate_connection(handle, Transport::LE);
+        conn.report_le_credit_conn_req(spsm, cids, mtu, mps, initial_credits, initiator, ts);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn report_le_credit_conn_rsp(
+        &mut self,
+        handle: ConnectionHandle,
+        result: LeCreditBasedConnectionResponseResult,
+        cids: &[CidInformation],
+        mtu: u16,
+        mps: u16,
+        initial_credits: u32,
+        initiator: InitiatorType,
+        ts: NaiveDateTime,
+    ) {
+        let conn = self.get_or_allocate_connection(handle, Transport::LE);
+        conn.report_le_credit_conn_rsp(result, cids, mtu, mps, initial_credits, initiator, ts);
+    }
+
+    fn report_le_flow_control_credit(
+        &mut self,
+        handle: ConnectionHandle,
+        cid: Cid,
+        credits: u16,
+        ts: NaiveDateTime,
+    ) {
+        let conn = self.get_or_allocate_connection(handle, Transport::LE);
+        conn.report_le_flow_control_credit(cid, credits, ts);
+    }
+
+    /// Finds the already-active `AclInformation` carrying `handle`, on whichever transport it's
+    /// connected over, without allocating a new one (unlike `get_or_allocate_connection`: ACL
+    /// data frames arrive constantly and must be cheap no-ops for handles we don't track).
+    fn find_active_connection_by_handle(&mut self, handle: ConnectionHandle) -> Option<&mut AclInformation> {
+        let (address, transport) = self.index.get(&ConnectionKey::AclHandle(handle))?;
+        let device = self.devices.get_mut(&address)?;
+        if device.is_connection_active(transport)
+            && device.acls[&transport].last().unwrap().handle == handle
+        {
+            return device.acls.get_mut(&transport).unwrap().last_mut();
+        }
+        None
+    }
+
+    /// Dispatches one ACL data (non-signaling) frame to whichever of the LE CoC credit tracking
+    /// or RFCOMM DLCI tracking applies to `cid` - each is a no-op if `cid` isn't one it owns.
+    fn report_acl_frame(
+        &mut self,
+        handle: ConnectionHandle,
+        cid: Cid,
+        payload: &[u8],
+        initiator: InitiatorType,
+        ts: NaiveDateTime,
+    ) {
+        if let Some(conn) = self.find_active_connection_by_handle(handle) {
+            conn.report_le_kframe(cid, payload.len(), ts);
+            conn.report_rfcomm_frame(cid, payload, initiator, ts);
+        }
+    }
+
+    /// Same data as `report`, as a JSON tree: devices (with per-transport ACL records and their
+    /// L2CAP channel/profile entries) in the same order the text report lists them, plus the
+    /// `unknown_connections` bucket.
+    pub fn report_json(&self) -> Value {
+        let mut addresses: Vec<Address> = self.devices.keys().cloned().collect();
+        addresses.sort_unstable_by(|a, b| sort_addresses(&self.devices[a], &self.devices[b]));
+
+        json!({
+            "devices": addresses.iter().map(|a| self.devices[a].to_json()).collect::<Vec<_>>(),
+            "unknown_connections": self
+                .unknown_connections
+                .values()
+                .map(AclInformation::to_json)
+                .collect::<Vec<_>>(),
+        })
     }
 }
 
@@ -853,9 +2037,21 @@ impl Rule for InformationalRule {
                 }
 
                 EventChild::SynchronousConnectionComplete(ev) => {
+                    let link_type = match ev.get_link_type() {
+                        LinkType::Esco => ScoLinkType::Esco,
+                        _ => ScoLinkType::Sco,
+                    };
+                    let codec = match ev.get_air_mode() {
+                        AirMode::Cvsd => ScoCodec::Cvsd,
+                        AirMode::TransparentData => ScoCodec::Transparent,
+                        AirMode::ALaw => ScoCodec::ALaw,
+                        AirMode::ULaw => ScoCodec::ULaw,
+                    };
                     self.report_sco_connection_start(
                         &ev.get_bd_addr(),
                         ev.get_connection_handle(),
+                        link_type,
+                        codec,
                         packet.ts,
                     );
                     // If failed, assume it's the end of connection.
@@ -874,12 +2070,38 @@ impl Rule for InformationalRule {
                     self.pending_disconnections.remove(&handle);
                 }
 
+                EventChild::AuthenticationComplete(ev) => {
+                    self.report_acl_lifecycle_event_for_handle(
+                        ev.get_connection_handle(),
+                        AclLifecycleInput::AuthenticationComplete,
+                        packet.ts,
+                    );
+                }
+
+                EventChild::EncryptionChange(ev) => {
+                    self.report_acl_lifecycle_event_for_handle(
+                        ev.get_connection_handle(),
+                        AclLifecycleInput::EncryptionChanged,
+                        packet.ts,
+                    );
+                }
+
+                EventChild::RoleChange(ev) => {
+                    self.report_acl_lifecycle_event(
+                        &ev.get_bd_addr(),
+                        Transport::BREDR,
+                        AclLifecycleInput::RoleChangeComplete,
+                        packet.ts,
+                    );
+                }
+
                 EventChild::ExtendedInquiryResult(ev) => {
                     self.process_raw_gap_data(
                         &ev.get_address(),
                         ev.get_extended_inquiry_response(),
+                        packet.ts,
                     );
-                    self.report_address_type(&ev.get_address(), AddressType::BREDR);
+                    self.report_address_type(&ev.get_address(), AddressType::BREDR, packet.ts);
                 }
 
                 EventChild::RemoteNameRequestComplete(ev) => {
@@ -888,8 +2110,8 @@ impl Rule for InformationalRule {
                     }
                     let name = String::from_utf8_lossy(ev.get_remote_name());
                     let name = name.trim_end_matches(char::from(0));
-                    self.report_name(&ev.get_bd_addr(), &name.to_owned());
-                    self.report_address_type(&ev.get_bd_addr(), AddressType::BREDR);
+                    self.report_name(&ev.get_bd_addr(), &name.to_owned(), packet.ts);
+                    self.report_address_type(&ev.get_bd_addr(), AddressType::BREDR, packet.ts);
                 }
 
                 EventChild::LeMetaEvent(ev) => match ev.specialize() {
@@ -899,10 +2121,11 @@ impl Rule for InformationalRule {
                         }
 
                         // Determining LE initiator is complex, for simplicity assume host inits.
-                        self.report_acl_state(
+                        self.report_acl_lifecycle_event(
                             &ev.get_peer_address(),
                             Transport::LE,
-                            AclState::Initiating,
+                            AclLifecycleInput::InitiateConnection,
+                            packet.ts,
                         );
                         self.report_connection_start(
                             &ev.get_peer_address(),
@@ -910,7 +2133,7 @@ impl Rule for InformationalRule {
                             Transport::LE,
                             packet.ts,
                         );
-                        self.report_address_type(&ev.get_peer_address(), AddressType::LE);
+                        self.report_address_type(&ev.get_peer_address(), AddressType::LE, packet.ts);
                     }
 
                     LeMetaEventChild::LeEnhancedConnectionComplete(ev) => {
@@ -919,10 +2142,11 @@ impl Rule for InformationalRule {
                         }
 
                         // Determining LE initiator is complex, for simplicity assume host inits.
-                        self.report_acl_state(
+                        self.report_acl_lifecycle_event(
                             &ev.get_peer_address(),
                             Transport::LE,
-                            AclState::Initiating,
+                            AclLifecycleInput::InitiateConnection,
+                            packet.ts,
                         );
                         self.report_connection_start(
                             &ev.get_peer_address(),
@@ -930,20 +2154,20 @@ impl Rule for InformationalRule {
                             Transport::LE,
                             packet.ts,
                         );
-                        self.report_address_type(&ev.get_peer_address(), AddressType::LE);
+                        self.report_address_type(&ev.get_peer_address(), AddressType::LE, packet.ts);
                     }
 
                     LeMetaEventChild::LeAdvertisingReport(ev) => {
                         for resp in ev.get_responses() {
-                            self.process_raw_gap_data(&resp.address, &resp.advertising_data);
-                            self.report_address_type(&resp.address, AddressType::LE);
+                            self.process_raw_gap_data(&resp.address, &resp.advertising_data, packet.ts);
+                            self.report_address_type(&resp.address, AddressType::LE, packet.ts);
                         }
                     }
 
                     LeMetaEventChild::LeExtendedAdvertisingReport(ev) => {
                         for resp in ev.get_responses() {
-                            self.process_raw_gap_data(&resp.address, &resp.advertising_data);
-                            self.report_address_type(&resp.address, AddressType::LE);
+                            self.process_raw_gap_data(&resp.address, &resp.advertising_data, packet.ts);
+                            self.report_address_type(&resp.address, AddressType::LE, packet.ts);
                         }
                     }
 
@@ -960,26 +2184,55 @@ impl Rule for InformationalRule {
                     self.report_reset(packet.ts);
                 }
                 CommandChild::CreateConnection(cmd) => {
-                    self.report_acl_state(
+                    self.report_acl_lifecycle_event(
                         &cmd.get_bd_addr(),
                         Transport::BREDR,
-                        AclState::Initiating,
+                        AclLifecycleInput::InitiateConnection,
+                        packet.ts,
                     );
-                    self.report_address_type(&cmd.get_bd_addr(), AddressType::BREDR);
+                    self.report_address_type(&cmd.get_bd_addr(), AddressType::BREDR, packet.ts);
                 }
                 CommandChild::AcceptConnectionRequest(cmd) => {
-                    self.report_acl_state(
+                    self.report_acl_lifecycle_event(
                         &cmd.get_bd_addr(),
                         Transport::BREDR,
-                        AclState::Accepting,
+                        AclLifecycleInput::AcceptConnection,
+                        packet.ts,
+                    );
+                    self.report_address_type(&cmd.get_bd_addr(), AddressType::BREDR, packet.ts);
+                }
+                CommandChild::AuthenticationRequested(cmd) => {
+                    self.report_acl_lifecycle_event_for_handle(
+                        cmd.get_connection_handle(),
+                        AclLifecycleInput::AuthenticationRequested,
+                        packet.ts,
+                    );
+                }
+                CommandChild::SetConnectionEncryption(cmd) => {
+                    self.report_acl_lifecycle_event_for_handle(
+                        cmd.get_connection_handle(),
+                        AclLifecycleInput::EncryptionRequested,
+                        packet.ts,
+                    );
+                }
+                CommandChild::SwitchRole(cmd) => {
+                    self.report_acl_lifecycle_event(
+                        &cmd.get_bd_addr(),
+                        Transport::BREDR,
+                        AclLifecycleInput::RoleSwitchRequested,
+                        packet.ts,
                     );
-                    self.report_address_type(&cmd.get_bd_addr(), AddressType::BREDR);
                 }
                 CommandChild::Disconnect(cmd) => {
                     // If reason is power off, the host might not wait for connection complete event
                     let is_power_off = cmd.get_reason()
                         == DisconnectReason::RemoteDeviceTerminatedConnectionPowerOff;
                     let handle = cmd.get_connection_handle();
+                    self.report_acl_lifecycle_event_for_handle(
+                        handle,
+                        AclLifecycleInput::DisconnectRequested,
+                        packet.ts,
+                    );
                     self.pending_disconnections.insert(handle, is_power_off);
                     if is_power_off {
                         self.report_connection_end(handle, packet.ts);
@@ -1022,11 +2275,92 @@ impl Rule for InformationalRule {
                                 packet.ts,
                             );
                         }
+                        ControlChild::LeCreditBasedConnectionRequest(creq) => {
+                            self.report_le_credit_conn_req(
+                                tx.get_handle(),
+                                creq.get_le_psm(),
+                                &[creq.get_source_cid()],
+                                creq.get_mtu(),
+                                creq.get_mps(),
+                                creq.get_initial_credits() as u32,
+                                InitiatorType::Host,
+                                packet.ts,
+                            );
+                        }
+                        ControlChild::LeCreditBasedConnectionResponse(crsp) => {
+                            self.report_le_credit_conn_rsp(
+                                tx.get_handle(),
+                                crsp.get_result(),
+                                &[CidInformation {
+                                    host_cid: crsp.get_destination_cid(),
+                                    peer_cid: crsp.get_source_cid(),
+                                }],
+                                crsp.get_mtu(),
+                                crsp.get_mps(),
+                                crsp.get_initial_credits() as u32,
+                                InitiatorType::Peer,
+                                packet.ts,
+                            );
+                        }
+                        ControlChild::CreditBasedConnectionRequest(creq) => {
+                            self.report_le_credit_conn_req(
+                                tx.get_handle(),
+                                creq.get_spsm(),
+                                creq.get_source_cids(),
+                                creq.get_mtu(),
+                                creq.get_mps(),
+                                creq.get_initial_credits() as u32,
+                                InitiatorType::Host,
+                                packet.ts,
+                            );
+                        }
+                        ControlChild::CreditBasedConnectionResponse(crsp) => {
+                            let cids: Vec<CidInformation> = crsp
+                                .get_destination_cids()
+                                .iter()
+                                .zip(crsp.get_source_cids().iter())
+                                .map(|(&destination_cid, &source_cid)| CidInformation {
+                                    host_cid: destination_cid,
+                                    peer_cid: source_cid,
+                                })
+                                .collect();
+                            self.report_le_credit_conn_rsp(
+                                tx.get_handle(),
+                                crsp.get_result(),
+                                &cids,
+                                crsp.get_mtu(),
+                                crsp.get_mps(),
+                                crsp.get_initial_credits() as u32,
+                                InitiatorType::Peer,
+                                packet.ts,
+                            );
+                        }
+                        ControlChild::LeFlowControlCredit(credit) => {
+                            self.report_le_flow_control_credit(
+                                tx.get_handle(),
+                                credit.get_cid(),
+                                credit.get_credits(),
+                                packet.ts,
+                            );
+                        }
 
                         // AclContent::Control.specialize()
                         _ => {}
                     },
 
+                    // A plain data frame: either a K-frame (first fragment of an SDU) on a Credit
+                    // Based channel, or an RFCOMM frame multiplexed over a classic PSM-3 channel.
+                    // CIDs that aren't tracked as either are silently ignored.
+                    AclContent::Frame(frame) => {
+                        self.report_acl_frame(
+                            tx.get_handle(),
+                            frame.get_cid(),
+                            frame.get_payload(),
+                            InitiatorType::Host,
+                            packet.ts,
+                        );
+                    }
+
                     // PacketChild::AclTx(tx).specialize()
                     _ => {}
                 }
@@ -1064,11 +2398,90 @@ impl Rule for InformationalRule {
                                 packet.ts,
                             );
                         }
+                        ControlChild::LeCreditBasedConnectionRequest(creq) => {
+                            self.report_le_credit_conn_req(
+                                rx.get_handle(),
+                                creq.get_le_psm(),
+                                &[creq.get_source_cid()],
+                                creq.get_mtu(),
+                                creq.get_mps(),
+                                creq.get_initial_credits() as u32,
+                                InitiatorType::Peer,
+                                packet.ts,
+                            );
+                        }
+                        ControlChild::LeCreditBasedConnectionResponse(crsp) => {
+                            self.report_le_credit_conn_rsp(
+                                rx.get_handle(),
+                                crsp.get_result(),
+                                &[CidInformation {
+                                    host_cid: crsp.get_source_cid(),
+                                    peer_cid: crsp.get_destination_cid(),
+                                }],
+                                crsp.get_mtu(),
+                                crsp.get_mps(),
+                                crsp.get_initial_credits() as u32,
+                                InitiatorType::Host,
+                                packet.ts,
+                            );
+                        }
+                        ControlChild::CreditBasedConnectionRequest(creq) => {
+                            self.report_le_credit_conn_req(
+                                rx.get_handle(),
+                                creq.get_spsm(),
+                                creq.get_source_cids(),
+                                creq.get_mtu(),
+                                creq.get_mps(),
+                                creq.get_initial_credits() as u32,
+                                InitiatorType::Peer,
+                                packet.ts,
+                            );
+                        }
+                        ControlChild::CreditBasedConnectionResponse(crsp) => {
+                            let cids: Vec<CidInformation> = crsp
+                                .get_source_cids()
+                                .iter()
+                                .zip(crsp.get_destination_cids().iter())
+                                .map(|(&source_cid, &destination_cid)| CidInformation {
+                                    host_cid: source_cid,
+                                    peer_cid: destination_cid,
+                                })
+                                .collect();
+                            self.report_le_credit_conn_rsp(
+                                rx.get_handle(),
+                                crsp.get_result(),
+                                &cids,
+                                crsp.get_mtu(),
+                                crsp.get_mps(),
+                                crsp.get_initial_credits() as u32,
+                                InitiatorType::Host,
+                                packet.ts,
+                            );
+                        }
+                        ControlChild::LeFlowControlCredit(credit) => {
+                            self.report_le_flow_control_credit(
+                                rx.get_handle(),
+                                credit.get_cid(),
+                                credit.get_credits(),
+                                packet.ts,
+                            );
+                        }
 
                         // AclContent::Control.specialize()
                         _ => {}
                     },
 
+                    // A plain data frame; see the matching arm under AclTx.
+                    AclContent::Frame(frame) => {
+                        self.report_acl_frame(
+                            rx.get_handle(),
+                            frame.get_cid(),
+                            frame.get_payload(),
+                            InitiatorType::Peer,
+                            packet.ts,
+                        );
+                    }
+
                     // PacketChild::AclRx(rx).specialize()
                     _ => {}
                 }
@@ -1080,50 +2493,6 @@ impl Rule for InformationalRule {
     }
 
     fn report(&self, writer: &mut dyn Write) {
-        /* Sort when displaying the addresses, from the most to the least important:
-         * (1) Device with connections > Device without connections
-         * (2) Device with known name > Device with unknown name
-         * (3) BREDR > LE > Dual
-         * (4) Name, lexicographically (case sensitive)
-         * (5) Address, alphabetically
-         */
-        fn sort_addresses(a: &DeviceInformation, b: &DeviceInformation) -> Ordering {
-            let a_empty = a.acls[&Transport::BREDR].is_empty() && a.acls[&Transport::LE].is_empty();
-            let b_empty = b.acls[&Transport::BREDR].is_empty() && b.acls[&Transport::LE].is_empty();
-            let connection_order = a_empty.cmp(&b_empty);
-            if connection_order != Ordering::Equal {
-                return connection_order;
-            }
-
-            let known_name_order = a.names.is_empty().cmp(&b.names.is_empty());
-            if known_name_order != Ordering::Equal {
-                return known_name_order;
-            }
-
-            let address_type_order = a.address_type.cmp(&b.address_type);
-            if address_type_order != Ordering::Equal {
-                return address_type_order;
-            }
-
-            let a_name = format!("{}", DeviceInformation::print_names(&a.names));
-            let b_name = format!("{}", DeviceInformation::print_names(&b.names));
-            let name_order = a_name.cmp(&b_name);
-            if name_order != Ordering::Equal {
-                return name_order;
-            }
-
-            let a_address = <[u8; 6]>::from(a.address);
-            let b_address = <[u8; 6]>::from(b.address);
-            for i in (0..6).rev() {
-                let address_order = a_address[i].cmp(&b_address[i]);
-                if address_order != Ordering::Equal {
-                    return address_order;
-                }
-            }
-            // This shouldn't be executed
-            return Ordering::Equal;
-        }
-
         if self.devices.is_empty() && self.unknown_connections.is_empty() {
             return;
         }
@@ -1148,7 +2517,7 @@ impl Rule for InformationalRule {
     }
 
     fn report_signals(&self) -> &[Signal] {
-        &[]
+        &self.signals
     }
 }
 
@@ -37,4 +37,14 @@ impl IBluetoothLogging for IBluetoothLoggingDBus {
     fn get_log_level(&self) -> Level {
         dbus_generated!()
     }
+
+    #[dbus_method("SetLogLevelForTag")]
+    fn set_log_level_for_tag(&mut self, tag: &str, level: Level) {
+        dbus_generated!()
+    }
+
+    #[dbus_method("GetLogLevelForTag")]
+    fn get_log_level_for_tag(&self, tag: &str) -> Option<Level> {
+        dbus_generated!()
+    }
 }
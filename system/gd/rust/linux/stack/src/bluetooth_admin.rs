@@ -3,6 +3,7 @@
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 use crate::bluetooth::{Bluetooth, BluetoothDevice, IBluetooth, IBluetoothCallback};
@@ -15,6 +16,7 @@ use crate::{APIMessage, BluetoothAPI, Message, RPCProxy};
 use bt_topshim::btif::{BtPropertyType, BtSspVariant, RawAddress, Uuid};
 use bt_topshim::profiles::sdp::BtSdpRecord;
 use log::{info, warn};
+use notify::{RecursiveMode, Watcher};
 use serde_json::{json, Value};
 use tokio::sync::mpsc::Sender;
 
@@ -26,6 +28,17 @@ pub trait IBluetoothAdmin {
     fn set_allowed_services(&mut self, services: Vec<Uuid>) -> bool;
     /// Get the allowlist in UUIDs
     fn get_allowed_services(&self) -> Vec<Uuid>;
+    /// Overwrite the current denylist and store it to a file.
+    fn set_denied_services(&mut self, services: Vec<(Uuid, DenyLevel)>) -> bool;
+    /// Get the denylist as (UUID, DenyLevel) pairs
+    fn get_denied_services(&self) -> Vec<(Uuid, DenyLevel)>;
+    /// Overwrite `device`'s allowlist override and store it to a file. An empty override, like an
+    /// empty global allowlist, means "allow everything".
+    fn set_device_allowed_services(&mut self, device: BluetoothDevice, services: Vec<Uuid>) -> bool;
+    /// Remove `device`'s allowlist override, falling back to the global allowlist for it again.
+    fn clear_device_allowed_services(&mut self, device: BluetoothDevice) -> bool;
+    /// Get `device`'s allowlist override, if it has one.
+    fn get_device_allowed_services(&self, device: BluetoothDevice) -> Option<Vec<Uuid>>;
     /// Get the PolicyEffect struct of a device
     fn get_device_policy_effect(&self, device: BluetoothDevice) -> Option<PolicyEffect>;
     /// Register client callback
@@ -37,11 +50,44 @@ pub trait IBluetoothAdmin {
     fn unregister_admin_policy_callback(&mut self, callback_id: u32) -> bool;
 }
 
+/// How strongly a service is denied, borrowed from the WebBluetooth blocklist model: a service
+/// can be hard-blocked, or left visible/connectable with only one direction of access suppressed.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum DenyLevel {
+    /// The service is never allowed, regardless of the allowlist.
+    Full,
+    /// The service is visible/connectable, but write-oriented profiles are suppressed.
+    ExcludeWrites,
+    /// The service is visible/connectable, but reads are suppressed.
+    ExcludeReads,
+}
+
+impl DenyLevel {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DenyLevel::Full => "full",
+            DenyLevel::ExcludeWrites => "exclude_writes",
+            DenyLevel::ExcludeReads => "exclude_reads",
+        }
+    }
+
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "full" => Some(DenyLevel::Full),
+            "exclude_writes" => Some(DenyLevel::ExcludeWrites),
+            "exclude_reads" => Some(DenyLevel::ExcludeReads),
+            _ => None,
+        }
+    }
+}
+
 /// Information of the effects to a remote device by the admin policies
 #[derive(PartialEq, Clone, Debug)]
 pub struct PolicyEffect {
     /// Array of services that are blocked by policy
     pub service_blocked: Vec<Uuid>,
+    /// Array of services that are denied by policy, along with the deny level applied
+    pub service_denied: Vec<(Uuid, DenyLevel)>,
     /// Indicate if the device has an adapter-supported profile that is blocked by the policy
     pub affected: bool,
 }
@@ -50,17 +96,51 @@ pub struct PolicyEffect {
 #[derive(Clone)]
 pub(crate) struct BluetoothAdminPolicyHelper {
     allowed_services: HashSet<Uuid>,
+    denied_services: HashMap<Uuid, DenyLevel>,
+    /// Per-device allowlist overrides. Following Servo's model, a device with an entry here uses
+    /// it instead of `allowed_services`, even if the entry is empty (allow everything for that
+    /// device specifically). Keyed by address rather than the full `BluetoothDevice`: the name is
+    /// irrelevant to the policy and reloading from disk (where only the address is persisted)
+    /// must produce the same key as a runtime lookup.
+    device_allowed_services: HashMap<RawAddress, HashSet<Uuid>>,
 }
 
 impl Default for BluetoothAdminPolicyHelper {
     fn default() -> Self {
-        Self { allowed_services: HashSet::default() }
+        Self {
+            allowed_services: HashSet::default(),
+            denied_services: HashMap::default(),
+            device_allowed_services: HashMap::default(),
+        }
     }
 }
 
 impl BluetoothAdminPolicyHelper {
+    fn allowlist_permits(allowed: &HashSet<Uuid>, service: &Uuid) -> bool {
+        allowed.is_empty() || allowed.contains(service)
+    }
+
     pub(crate) fn is_service_allowed(&self, service: &Uuid) -> bool {
-        self.allowed_services.is_empty() || self.allowed_services.contains(service)
+        if self.denied_services.get(service) == Some(&DenyLevel::Full) {
+            return false;
+        }
+        Self::allowlist_permits(&self.allowed_services, service)
+    }
+
+    /// Same as `is_service_allowed`, but consults `device`'s allowlist override if it has one,
+    /// falling back to the global allowlist otherwise.
+    pub(crate) fn is_service_allowed_for_device(
+        &self,
+        device: &BluetoothDevice,
+        service: &Uuid,
+    ) -> bool {
+        if self.denied_services.get(service) == Some(&DenyLevel::Full) {
+            return false;
+        }
+        match self.device_allowed_services.get(&device.address) {
+            Some(allowed) => Self::allowlist_permits(allowed, service),
+            None => Self::allowlist_permits(&self.allowed_services, service),
+        }
     }
 
     pub(crate) fn is_profile_allowed(&self, profile: &Profile) -> bool {
@@ -81,9 +161,80 @@ impl BluetoothAdminPolicyHelper {
         self.allowed_services.iter().cloned().collect()
     }
 
+    fn set_denied_services(&mut self, services: Vec<(Uuid, DenyLevel)>) -> bool {
+        let services: HashMap<Uuid, DenyLevel> = services.into_iter().collect();
+        if self.denied_services != services {
+            self.denied_services = services;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn get_denied_services(&self) -> Vec<(Uuid, DenyLevel)> {
+        self.denied_services.iter().map(|(uu, level)| (*uu, *level)).collect()
+    }
+
+    /// Returns the deny level of every `remote_uuids` entry that has one.
+    fn get_denied_services_for(&self, remote_uuids: &Vec<Uuid>) -> Vec<(Uuid, DenyLevel)> {
+        remote_uuids
+            .iter()
+            .filter_map(|uu| self.denied_services.get(uu).map(|level| (*uu, *level)))
+            .collect()
+    }
+
+    fn set_device_allowed_services(&mut self, device: BluetoothDevice, services: Vec<Uuid>) -> bool {
+        self.set_device_allowed_services_for_address(device.address, services)
+    }
+
+    /// Same as `set_device_allowed_services`, but for callers (e.g. config-file reload) that only
+    /// have the address on hand and shouldn't have to fabricate a `BluetoothDevice` to use it.
+    fn set_device_allowed_services_for_address(
+        &mut self,
+        address: RawAddress,
+        services: Vec<Uuid>,
+    ) -> bool {
+        let services: HashSet<Uuid> = services.into_iter().collect();
+        if self.device_allowed_services.get(&address) != Some(&services) {
+            self.device_allowed_services.insert(address, services);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn clear_device_allowed_services(&mut self, device: &BluetoothDevice) -> bool {
+        self.device_allowed_services.remove(&device.address).is_some()
+    }
+
+    fn get_device_allowed_services(&self, device: &BluetoothDevice) -> Option<Vec<Uuid>> {
+        self.device_allowed_services
+            .get(&device.address)
+            .map(|services| services.iter().cloned().collect())
+    }
+
+    fn get_all_device_allowed_services(&self) -> Vec<(RawAddress, Vec<Uuid>)> {
+        self.device_allowed_services
+            .iter()
+            .map(|(address, services)| (*address, services.iter().cloned().collect()))
+            .collect()
+    }
+
     fn get_blocked_services(&self, remote_uuids: &Vec<Uuid>) -> Vec<Uuid> {
         remote_uuids.iter().filter(|&uu| !self.is_service_allowed(uu)).cloned().collect()
     }
+
+    fn get_blocked_services_for_device(
+        &self,
+        device: &BluetoothDevice,
+        remote_uuids: &Vec<Uuid>,
+    ) -> Vec<Uuid> {
+        remote_uuids
+            .iter()
+            .filter(|&uu| !self.is_service_allowed_for_device(device, uu))
+            .cloned()
+            .collect()
+    }
 }
 
 pub trait IBluetoothAdminPolicyCallback: RPCProxy {
@@ -110,6 +261,10 @@ pub struct BluetoothAdmin {
     callbacks: Callbacks<dyn IBluetoothAdminPolicyCallback + Send>,
     device_policy_affect_cache: HashMap<BluetoothDevice, Option<PolicyEffect>>,
     tx: Sender<Message>,
+    /// Shared secret gating `register_admin_policy_callback` via
+    /// [`RPCProxy::export_for_rpc_if_authenticated`]. `None` preserves today's trust-every-caller
+    /// behavior for deployments that don't configure one.
+    rpc_secret: Option<String>,
 }
 
 impl BluetoothAdmin {
@@ -119,6 +274,7 @@ impl BluetoothAdmin {
         adapter: Arc<Mutex<Box<Bluetooth>>>,
         bluetooth_media: Arc<Mutex<Box<BluetoothMedia>>>,
         socket_manager: Arc<Mutex<Box<BluetoothSocketManager>>>,
+        rpc_secret: Option<String>,
     ) -> Self {
         Self {
             path,
@@ -129,6 +285,7 @@ impl BluetoothAdmin {
             callbacks: Callbacks::new(tx.clone(), Message::AdminCallbackDisconnected),
             device_policy_affect_cache: HashMap::new(),
             tx,
+            rpc_secret,
         }
     }
 
@@ -153,9 +310,10 @@ impl BluetoothAdmin {
         }
 
         // Now toggle the profiles based on the loaded config.
-        self.adapter.lock().unwrap().handle_admin_policy_changed(self.admin_helper.clone());
-        self.bluetooth_media.lock().unwrap().handle_admin_policy_changed(self.admin_helper.clone());
-        self.socket_manager.lock().unwrap().handle_admin_policy_changed(self.admin_helper.clone());
+        self.propagate_policy_change();
+
+        // Pick up out-of-band edits to the config file without requiring a restart.
+        self.start_config_watcher();
 
         // DBus API is ready now.
         tokio::spawn(async move {
@@ -163,8 +321,8 @@ impl BluetoothAdmin {
         });
     }
 
-    fn get_blocked_services(&self, remote_uuids: &Vec<Uuid>) -> Vec<Uuid> {
-        self.admin_helper.get_blocked_services(remote_uuids)
+    fn get_blocked_services(&self, device: &BluetoothDevice, remote_uuids: &Vec<Uuid>) -> Vec<Uuid> {
+        self.admin_helper.get_blocked_services_for_device(device, remote_uuids)
     }
 
     fn get_affected_status(&self, blocked_services: &Vec<Uuid>) -> bool {
@@ -178,53 +336,284 @@ impl BluetoothAdmin {
             .is_some()
     }
 
-    fn load_config(&mut self) -> Result<()> {
+    fn read_config_from_disk(
+        &self,
+    ) -> Result<(Vec<Uuid>, Vec<(Uuid, DenyLevel)>, Vec<(RawAddress, Vec<Uuid>)>)> {
         let mut file = File::open(&self.path)?;
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
         let json = serde_json::from_str::<Value>(contents.as_str())?;
-        let allowed_services = Self::get_config_from_json(&json)
-            .ok_or(Error::new(ErrorKind::Other, "Failed converting json to config"))?;
-        if !self.admin_helper.set_allowed_services(allowed_services) {
+        Self::get_config_from_json(&json)
+            .ok_or(Error::new(ErrorKind::Other, "Failed converting json to config"))
+    }
+
+    fn load_config(&mut self) -> Result<()> {
+        let (allowed_services, denied_services, device_allowed_services) =
+            self.read_config_from_disk()?;
+        let allowed_changed = self.admin_helper.set_allowed_services(allowed_services);
+        let denied_changed = self.admin_helper.set_denied_services(denied_services);
+        let mut device_overrides_changed = false;
+        for (address, services) in device_allowed_services {
+            device_overrides_changed |=
+                self.admin_helper.set_device_allowed_services_for_address(address, services);
+        }
+        if !allowed_changed && !denied_changed && !device_overrides_changed {
             info!("Admin: load_config: Unchanged");
         }
         Ok(())
     }
 
-    fn get_config_from_json(json: &Value) -> Option<Vec<Uuid>> {
-        Some(
-            json.get("allowed_services")?
-                .as_array()?
-                .iter()
-                .filter_map(|v| Uuid::from_string(v.as_str()?))
-                .collect(),
-        )
+    /// Spawns a background thread that watches `self.path` for on-disk modifications (e.g. an
+    /// admin pushing a new config out-of-band) and feeds `OnConfigFileChanged` back through the
+    /// single owning task so all resulting mutation stays serialized with `write_config`.
+    ///
+    /// Watches the parent directory rather than the file itself: `write_config` (and editors that
+    /// write atomically) replace the config file's inode via rename, and an inotify watch on the
+    /// old inode goes silently deaf once it's unlinked. Watching the directory survives the
+    /// rename; we just filter events down to the config file's name.
+    fn start_config_watcher(&self) {
+        let tx = self.tx.clone();
+        let path = self.path.clone();
+        std::thread::spawn(move || {
+            let config_path = Path::new(&path);
+            let watch_dir = match config_path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+                _ => Path::new(".").to_path_buf(),
+            };
+            let file_name = match config_path.file_name() {
+                Some(name) => name.to_owned(),
+                None => {
+                    warn!("Admin: Config path {} has no file name to watch", &path);
+                    return;
+                }
+            };
+
+            let (watcher_tx, watcher_rx) = std::sync::mpsc::channel();
+            let mut watcher = match notify::recommended_watcher(watcher_tx) {
+                Ok(watcher) => watcher,
+                Err(e) => {
+                    warn!("Admin: Failed to create config file watcher: {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = watcher.watch(&watch_dir, RecursiveMode::NonRecursive) {
+                warn!("Admin: Failed to watch config directory {:?}: {}", &watch_dir, e);
+                return;
+            }
+
+            for res in watcher_rx {
+                let event = match res {
+                    Ok(event) => event,
+                    Err(e) => {
+                        warn!("Admin: Config file watch error: {}", e);
+                        continue;
+                    }
+                };
+                if !event.kind.is_modify() && !event.kind.is_create() {
+                    continue;
+                }
+                if !event.paths.iter().any(|p| p.file_name() == Some(file_name.as_os_str())) {
+                    continue;
+                }
+                if tx.blocking_send(Message::AdminActions(AdminActions::OnConfigFileChanged)).is_err()
+                {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Re-reads `self.path` after an on-disk change and, if it differs from the in-memory state,
+    /// runs the same propagation path as [`IBluetoothAdmin::set_allowed_services`] without
+    /// writing the file back (we just read it; writing it again would race the next external
+    /// edit).
+    fn on_config_file_changed(&mut self) {
+        let (allowed_services, denied_services, device_allowed_services) =
+            match self.read_config_from_disk() {
+                Ok(config) => config,
+                Err(e) => {
+                    warn!("Admin: Failed to reload changed config file {}: {}", &self.path, e);
+                    return;
+                }
+            };
+
+        let allowed_changed = self.admin_helper.set_allowed_services(allowed_services);
+        let denied_changed = self.admin_helper.set_denied_services(denied_services);
+        let mut device_overrides_changed = false;
+        for (address, services) in device_allowed_services {
+            device_overrides_changed |=
+                self.admin_helper.set_device_allowed_services_for_address(address, services);
+        }
+
+        if !allowed_changed && !denied_changed && !device_overrides_changed {
+            return;
+        }
+
+        info!("Admin: Reloaded changed config file {}", &self.path);
+        self.propagate_policy_change();
+
+        if allowed_changed {
+            let allowed_services = self.admin_helper.get_allowed_services();
+            self.callbacks.for_all_callbacks(|cb| {
+                cb.on_service_allowlist_changed(allowed_services.clone());
+            });
+        }
+
+        self.recompute_device_policy_effects();
     }
 
+    fn get_config_from_json(
+        json: &Value,
+    ) -> Option<(Vec<Uuid>, Vec<(Uuid, DenyLevel)>, Vec<(RawAddress, Vec<Uuid>)>)> {
+        // Configs written before the denylist/per-device fields existed don't have a "version"
+        // key at all; treat that as version 1.
+        let version = json.get("version").and_then(|v| v.as_u64()).unwrap_or(1);
+
+        let allowed_services = json
+            .get("allowed_services")?
+            .as_array()?
+            .iter()
+            .filter_map(|v| Uuid::from_string(v.as_str()?))
+            .collect();
+
+        if version < 2 {
+            // v1 files predate the denylist/per-device overrides; there's nothing more to parse.
+            return Some((allowed_services, Vec::new(), Vec::new()));
+        }
+
+        let denied_services = json
+            .get("denied_services")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| {
+                        let uuid = Uuid::from_string(v.get("uuid")?.as_str()?)?;
+                        let level = DenyLevel::from_str(v.get("level")?.as_str()?)?;
+                        Some((uuid, level))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let device_allowed_services = json
+            .get("device_allowed_services")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| {
+                        let address = RawAddress::from_string(v.get("address")?.as_str()?)?;
+                        let services = v
+                            .get("services")?
+                            .as_array()?
+                            .iter()
+                            .filter_map(|v| Uuid::from_string(v.as_str()?))
+                            .collect();
+                        Some((address, services))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some((allowed_services, denied_services, device_allowed_services))
+    }
+
+    /// Current on-disk config schema version. Bump this when the JSON shape changes and teach
+    /// `get_config_from_json` how to read the old shape, so older files are upgraded in place the
+    /// next time `write_config` runs rather than failing to load.
+    const CONFIG_VERSION: u64 = 2;
+
     fn write_config(&self) -> Result<()> {
-        let mut f = File::create(&self.path)?;
+        // Write to a sibling temp file and rename over the real path so a reader (including our
+        // own config file watcher) never observes a partially-written file.
+        let tmp_path = format!("{}.tmp", &self.path);
+        let mut f = File::create(&tmp_path)?;
         f.write_all(
-            Self::get_config_json_string(self.admin_helper.get_allowed_services()).as_bytes(),
-        )
+            Self::get_config_json_string(
+                self.admin_helper.get_allowed_services(),
+                self.admin_helper.get_denied_services(),
+                self.admin_helper.get_all_device_allowed_services(),
+            )
+            .as_bytes(),
+        )?;
+        f.sync_all()?;
+        std::fs::rename(&tmp_path, &self.path)
     }
 
-    fn get_config_json_string(uuids: Vec<Uuid>) -> String {
+    fn get_config_json_string(
+        uuids: Vec<Uuid>,
+        denied_services: Vec<(Uuid, DenyLevel)>,
+        device_allowed_services: Vec<(RawAddress, Vec<Uuid>)>,
+    ) -> String {
         serde_json::to_string_pretty(&json!({
+            "version": Self::CONFIG_VERSION,
             "allowed_services":
                 uuids
                     .iter()
                     .map(|uu| uu.to_string())
-                    .collect::<Vec<String>>()
+                    .collect::<Vec<String>>(),
+            "denied_services":
+                denied_services
+                    .iter()
+                    .map(|(uu, level)| json!({"uuid": uu.to_string(), "level": level.as_str()}))
+                    .collect::<Vec<Value>>(),
+            "device_allowed_services":
+                device_allowed_services
+                    .iter()
+                    .map(|(address, services)| json!({
+                        "address": address.to_string(),
+                        "services": services.iter().map(|uu| uu.to_string()).collect::<Vec<String>>(),
+                    }))
+                    .collect::<Vec<Value>>(),
         }))
         .ok()
         .unwrap()
     }
 
-    fn new_device_policy_effect(&self, uuids: Option<Vec<Uuid>>) -> Option<PolicyEffect> {
+    /// Pushes the current allowlist/denylist down to the adapter, media, and socket manager, the
+    /// places that actually gate service access.
+    fn propagate_policy_change(&self) {
+        self.adapter.lock().unwrap().handle_admin_policy_changed(self.admin_helper.clone());
+        self.bluetooth_media.lock().unwrap().handle_admin_policy_changed(self.admin_helper.clone());
+        self.socket_manager.lock().unwrap().handle_admin_policy_changed(self.admin_helper.clone());
+    }
+
+    /// Recomputes `PolicyEffect` for `device`, if it's known, and notifies callbacks if it
+    /// changed.
+    fn recompute_device_policy_effect(&mut self, device: &BluetoothDevice) {
+        let effect = match self.device_policy_affect_cache.get(device) {
+            Some(effect) => effect.clone(),
+            None => return,
+        };
+        let uuids = self.adapter.lock().unwrap().get_remote_uuids(device.clone());
+        let new_effect = self.new_device_policy_effect(device, Some(uuids));
+
+        if new_effect != effect {
+            self.callbacks.for_all_callbacks(|cb| {
+                cb.on_device_policy_effect_changed(device.clone(), new_effect.clone())
+            });
+            self.device_policy_affect_cache.insert(device.clone(), new_effect);
+        }
+    }
+
+    /// Recomputes `PolicyEffect` for every device in `device_policy_affect_cache` and notifies
+    /// callbacks of any that changed, e.g. after the allowlist or denylist changes.
+    fn recompute_device_policy_effects(&mut self) {
+        let devices: Vec<BluetoothDevice> = self.device_policy_affect_cache.keys().cloned().collect();
+        for device in devices {
+            self.recompute_device_policy_effect(&device);
+        }
+    }
+
+    fn new_device_policy_effect(
+        &self,
+        device: &BluetoothDevice,
+        uuids: Option<Vec<Uuid>>,
+    ) -> Option<PolicyEffect> {
         uuids.map(|uuids| {
-            let service_blocked = self.get_blocked_services(&uuids);
+            let service_blocked = self.get_blocked_services(device, &uuids);
+            let service_denied = self.admin_helper.get_denied_services_for(&uuids);
             let affected = self.get_affected_status(&service_blocked);
-            PolicyEffect { service_blocked, affected }
+            PolicyEffect { service_blocked, service_denied, affected }
         })
     }
 
@@ -246,7 +635,7 @@ impl BluetoothAdmin {
         remote_device: &BluetoothDevice,
         new_uuids: Vec<Uuid>,
     ) {
-        let new_effect = self.new_device_policy_effect(Some(new_uuids));
+        let new_effect = self.new_device_policy_effect(remote_device, Some(new_uuids));
         let cur_effect = self.device_policy_affect_cache.get(remote_device);
 
         if cur_effect.is_none() || *cur_effect.unwrap() != new_effect.clone() {
@@ -266,6 +655,7 @@ impl BluetoothAdmin {
                     self.adapter.lock().unwrap().get_remote_uuids(remote_device.clone());
                 self.on_device_uuid_changed(&remote_device, new_uuids);
             }
+            AdminActions::OnConfigFileChanged => self.on_config_file_changed(),
         }
     }
 }
@@ -281,9 +671,7 @@ impl IBluetoothAdmin for BluetoothAdmin {
             return true;
         }
 
-        self.adapter.lock().unwrap().handle_admin_policy_changed(self.admin_helper.clone());
-        self.bluetooth_media.lock().unwrap().handle_admin_policy_changed(self.admin_helper.clone());
-        self.socket_manager.lock().unwrap().handle_admin_policy_changed(self.admin_helper.clone());
+        self.propagate_policy_change();
 
         if let Err(e) = self.write_config() {
             warn!("Admin: Failed to write config: {}", e);
@@ -296,17 +684,7 @@ impl IBluetoothAdmin for BluetoothAdmin {
             cb.on_service_allowlist_changed(allowed_services.clone());
         });
 
-        for (device, effect) in self.device_policy_affect_cache.clone().iter() {
-            let uuids = self.adapter.lock().unwrap().get_remote_uuids(device.clone());
-            let new_effect = self.new_device_policy_effect(Some(uuids));
-
-            if new_effect.clone() != *effect {
-                self.callbacks.for_all_callbacks(|cb| {
-                    cb.on_device_policy_effect_changed(device.clone(), new_effect.clone())
-                });
-                self.device_policy_affect_cache.insert(device.clone(), new_effect.clone());
-            }
-        }
+        self.recompute_device_policy_effects();
 
         true
     }
@@ -315,6 +693,67 @@ impl IBluetoothAdmin for BluetoothAdmin {
         self.admin_helper.get_allowed_services()
     }
 
+    fn set_denied_services(&mut self, services: Vec<(Uuid, DenyLevel)>) -> bool {
+        if !self.admin_helper.set_denied_services(services) {
+            // Denylist is not changed.
+            return true;
+        }
+
+        self.propagate_policy_change();
+
+        if let Err(e) = self.write_config() {
+            warn!("Admin: Failed to write config: {}", e);
+        } else {
+            info!("Admin: Write settings into {} successfully", &self.path);
+        }
+
+        self.recompute_device_policy_effects();
+
+        true
+    }
+
+    fn get_denied_services(&self) -> Vec<(Uuid, DenyLevel)> {
+        self.admin_helper.get_denied_services()
+    }
+
+    fn set_device_allowed_services(&mut self, device: BluetoothDevice, services: Vec<Uuid>) -> bool {
+        if !self.admin_helper.set_device_allowed_services(device.clone(), services) {
+            // Override is not changed.
+            return true;
+        }
+
+        if let Err(e) = self.write_config() {
+            warn!("Admin: Failed to write config: {}", e);
+        } else {
+            info!("Admin: Write settings into {} successfully", &self.path);
+        }
+
+        self.recompute_device_policy_effect(&device);
+
+        true
+    }
+
+    fn clear_device_allowed_services(&mut self, device: BluetoothDevice) -> bool {
+        if !self.admin_helper.clear_device_allowed_services(&device) {
+            // There was no override to clear.
+            return true;
+        }
+
+        if let Err(e) = self.write_config() {
+            warn!("Admin: Failed to write config: {}", e);
+        } else {
+            info!("Admin: Write settings into {} successfully", &self.path);
+        }
+
+        self.recompute_device_policy_effect(&device);
+
+        true
+    }
+
+    fn get_device_allowed_services(&self, device: BluetoothDevice) -> Option<Vec<Uuid>> {
+        self.admin_helper.get_device_allowed_services(&device)
+    }
+
     fn get_device_policy_effect(&self, device: BluetoothDevice) -> Option<PolicyEffect> {
         if let Some(effect) = self.device_policy_affect_cache.get(&device) {
             effect.clone()
@@ -326,8 +765,11 @@ impl IBluetoothAdmin for BluetoothAdmin {
 
     fn register_admin_policy_callback(
         &mut self,
-        callback: Box<dyn IBluetoothAdminPolicyCallback + Send>,
+        mut callback: Box<dyn IBluetoothAdminPolicyCallback + Send>,
     ) -> u32 {
+        if !callback.export_for_rpc_if_authenticated(self.rpc_secret.as_deref()) {
+            return 0;
+        }
         self.callbacks.add_callback(callback)
     }
 
@@ -340,6 +782,7 @@ pub enum AdminActions {
     OnDeviceFound(BluetoothDevice),
     OnDeviceCleared(BluetoothDevice),
     OnDeviceUuidChanged(BluetoothDevice),
+    OnConfigFileChanged,
 }
 
 /// Handles the callbacks from Bluetooth Device
@@ -420,7 +863,8 @@ impl RPCProxy for BluetoothDeviceCallbacks {
 
 #[cfg(test)]
 mod tests {
-    use crate::bluetooth_admin::{BluetoothAdmin, BluetoothAdminPolicyHelper};
+    use crate::bluetooth::BluetoothDevice;
+    use crate::bluetooth_admin::{BluetoothAdmin, BluetoothAdminPolicyHelper, DenyLevel};
     use bt_topshim::btif::Uuid;
     use serde_json::{json, Value};
 
@@ -459,7 +903,12 @@ mod tests {
         admin_helper: &BluetoothAdminPolicyHelper,
     ) -> Vec<String> {
         let mut v = serde_json::from_str::<Value>(
-            BluetoothAdmin::get_config_json_string(admin_helper.get_allowed_services()).as_str(),
+            BluetoothAdmin::get_config_json_string(
+                admin_helper.get_allowed_services(),
+                admin_helper.get_denied_services(),
+                admin_helper.get_all_device_allowed_services(),
+            )
+            .as_str(),
         )
         .unwrap()
         .get("allowed_services")
@@ -500,7 +949,7 @@ mod tests {
             BluetoothAdmin::get_config_from_json(&json!({
                 "allowed_services": allowed_services_str.clone()
             }))
-            .map(|uuids| admin_helper.set_allowed_services(uuids)),
+            .map(|(uuids, _, _)| admin_helper.set_allowed_services(uuids)),
             Some(true)
         );
         assert_eq!(get_sorted_allowed_services(&admin_helper), allowed_services_uuid);
@@ -509,11 +958,119 @@ mod tests {
         // invalid configuration
         assert_eq!(
             BluetoothAdmin::get_config_from_json(&json!({ "allowed_services": a2dp_sink_str }))
-                .map(|uuids| admin_helper.set_allowed_services(uuids)),
+                .map(|(uuids, _, _)| admin_helper.set_allowed_services(uuids)),
             None
         );
         // config should remain unchanged
         assert_eq!(get_sorted_allowed_services(&admin_helper), allowed_services_uuid);
         assert_eq!(get_sorted_allowed_services_from_config(&admin_helper), allowed_services_str);
     }
+
+    #[test]
+    fn test_set_denied_services() {
+        let mut admin_helper = BluetoothAdminPolicyHelper::default();
+        let uuid1: Uuid = [1; 16].into();
+        let uuid2: Uuid = [2; 16].into();
+
+        // Default admin denies nothing
+        assert!(admin_helper.is_service_allowed(&uuid1));
+        assert!(admin_helper.is_service_allowed(&uuid2));
+
+        admin_helper.set_denied_services(vec![(uuid1, DenyLevel::Full)]);
+
+        // A Full deny wins over the (empty, allow-all) allowlist.
+        assert!(!admin_helper.is_service_allowed(&uuid1));
+        assert!(admin_helper.is_service_allowed(&uuid2));
+        assert_eq!(admin_helper.get_blocked_services(&vec![uuid1, uuid2]), vec![uuid1]);
+
+        // A Full deny also wins even when the allowlist would otherwise allow the service.
+        admin_helper.set_allowed_services(vec![uuid1]);
+        assert!(!admin_helper.is_service_allowed(&uuid1));
+
+        // A partial deny (ExcludeWrites/ExcludeReads) doesn't affect is_service_allowed.
+        admin_helper.set_denied_services(vec![(uuid2, DenyLevel::ExcludeWrites)]);
+        assert!(admin_helper.is_service_allowed(&uuid2));
+        assert_eq!(
+            admin_helper.get_denied_services_for(&vec![uuid2]),
+            vec![(uuid2, DenyLevel::ExcludeWrites)]
+        );
+    }
+
+    #[test]
+    fn test_denied_services_config_roundtrip() {
+        let mut admin_helper = BluetoothAdminPolicyHelper::default();
+        let uuid1: Uuid = [1; 16].into();
+        let uuid2: Uuid = [2; 16].into();
+        admin_helper.set_denied_services(vec![(uuid1, DenyLevel::Full), (uuid2, DenyLevel::ExcludeReads)]);
+
+        let config_str = BluetoothAdmin::get_config_json_string(
+            admin_helper.get_allowed_services(),
+            admin_helper.get_denied_services(),
+            admin_helper.get_all_device_allowed_services(),
+        );
+        let json = serde_json::from_str::<Value>(config_str.as_str()).unwrap();
+        let (_, mut denied_services, _) = BluetoothAdmin::get_config_from_json(&json).unwrap();
+        denied_services.sort_by(|(lhs, _), (rhs, _)| lhs.uu.cmp(&rhs.uu));
+
+        assert_eq!(
+            denied_services,
+            vec![(uuid1, DenyLevel::Full), (uuid2, DenyLevel::ExcludeReads)]
+        );
+
+        // Configs without a "denied_services" key (older schema) parse as an empty denylist.
+        let (_, denied_services, _) =
+            BluetoothAdmin::get_config_from_json(&json!({ "allowed_services": [] })).unwrap();
+        assert_eq!(denied_services, Vec::new());
+    }
+
+    #[test]
+    fn test_config_version_upgrade() {
+        let uuid1: Uuid = [1; 16].into();
+
+        // A v1 file (no "version" key) has a denylist entry ignored, since the denylist didn't
+        // exist yet in that schema.
+        let (allowed_services, denied_services, device_allowed_services) =
+            BluetoothAdmin::get_config_from_json(&json!({
+                "allowed_services": [],
+                "denied_services": [{"uuid": uuid1.to_string(), "level": "full"}],
+            }))
+            .unwrap();
+        assert_eq!(allowed_services, Vec::new());
+        assert_eq!(denied_services, Vec::new());
+        assert_eq!(device_allowed_services, Vec::new());
+
+        // A file at the current version honors the same denylist entry.
+        let (_, denied_services, _) = BluetoothAdmin::get_config_from_json(&json!({
+            "version": BluetoothAdmin::CONFIG_VERSION,
+            "allowed_services": [],
+            "denied_services": [{"uuid": uuid1.to_string(), "level": "full"}],
+        }))
+        .unwrap();
+        assert_eq!(denied_services, vec![(uuid1, DenyLevel::Full)]);
+    }
+
+    #[test]
+    fn test_device_allowed_services_override() {
+        let mut admin_helper = BluetoothAdminPolicyHelper::default();
+        let uuid1: Uuid = [1; 16].into();
+        let uuid2: Uuid = [2; 16].into();
+        let device = BluetoothDevice { address: [1, 2, 3, 4, 5, 6].into(), name: "".to_string() };
+
+        admin_helper.set_allowed_services(vec![uuid1]);
+        // No override yet: falls back to the global allowlist.
+        assert!(admin_helper.is_service_allowed_for_device(&device, &uuid1));
+        assert!(!admin_helper.is_service_allowed_for_device(&device, &uuid2));
+
+        admin_helper.set_device_allowed_services(device.clone(), vec![uuid2]);
+        // With an override, the global allowlist no longer applies to this device.
+        assert!(!admin_helper.is_service_allowed_for_device(&device, &uuid1));
+        assert!(admin_helper.is_service_allowed_for_device(&device, &uuid2));
+        assert_eq!(admin_helper.get_device_allowed_services(&device), Some(vec![uuid2]));
+
+        admin_helper.clear_device_allowed_services(&device);
+        // Falls back to the global allowlist again.
+        assert!(admin_helper.is_service_allowed_for_device(&device, &uuid1));
+        assert!(!admin_helper.is_service_allowed_for_device(&device, &uuid2));
+        assert_eq!(admin_helper.get_device_allowed_services(&device), None);
+    }
 }
@@ -4,12 +4,22 @@
 //! emitted from Rust or C/C++. In order to keep log levels in sync between the
 //! two, the |BluetoothLogging| struct will configure both the Rust logging and
 //! the C/C++ logging (via topshim).
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
 use bt_topshim::syslog::{set_default_log_level, set_log_level_for_tag, Level};
+use log::{Log, Metadata, Record};
 use log::LevelFilter;
-use syslog::{BasicLogger, Error, Facility, Formatter3164};
+use syslog::Error;
 
 use log_panics;
 
+use crate::log_sink::{LogSink, RotatingFileSink, StderrSink, SyslogSink};
+use crate::snoop::SnoopWriter;
+
+/// Default capture path used when verbose debug logging implicitly enables btsnoop.
+const DEFAULT_HCI_SNOOP_LOG_PATH: &str = "/var/log/bluetooth/btsnoop_hci.log";
+
 /// API to modify log levels that is exposed via RPC.
 pub trait IBluetoothLogging {
     /// Check whether debug logging is enabled.
@@ -23,98 +33,340 @@ pub trait IBluetoothLogging {
 
     /// Get the log level.
     fn get_log_level(&self) -> Level;
+
+    /// Set the log level for a specific tag (i.e. a `log::Record`'s target).
+    fn set_log_level_for_tag(&mut self, tag: &str, level: Level);
+
+    /// Get the log level override for a specific tag, if any is set.
+    fn get_log_level_for_tag(&self, tag: &str) -> Option<Level>;
+
+    /// Start (`Some(path)`) or stop (`None`) capturing HCI traffic to a btsnoop file.
+    fn set_hci_snoop_enabled(&mut self, path: Option<String>);
+}
+
+/// A single appender to enable, selected via the comma-separated `log_output` spec given to
+/// `BluetoothLogging::new`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SinkKind {
+    Stderr,
+    Syslog,
+}
+
+/// Configuration for the optional rotating-file sink, set via `enable_rotating_file_sink`.
+struct RotatingFileSinkConfig {
+    path: String,
+    max_bytes: u64,
+    backup_count: u32,
+}
+
+/// The top-level `log::Log` implementation registered with the `log` crate. It gates records on
+/// the combined default/per-tag level (to cheaply drop records no sink could possibly want), then
+/// fans surviving records out to every configured `LogSink`, each of which applies its own level
+/// filter.
+struct BluetoothLogDispatcher {
+    sinks: Vec<Arc<dyn LogSink>>,
+    default_level: Mutex<LevelFilter>,
+    tag_levels: Arc<Mutex<HashMap<String, LevelFilter>>>,
+}
+
+impl Log for BluetoothLogDispatcher {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let effective_level = self
+            .tag_levels
+            .lock()
+            .unwrap()
+            .get(record.target())
+            .cloned()
+            .unwrap_or(*self.default_level.lock().unwrap());
+        if record.level() > effective_level {
+            return;
+        }
+        for sink in &self.sinks {
+            sink.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        for sink in &self.sinks {
+            sink.flush();
+        }
+    }
 }
 
 /// Logging related implementation.
 pub struct BluetoothLogging {
-    /// Current log level
-    /// If the level is not verbose, `VERBOSE_ONLY_LOG_TAGS` will be set to emit up to `INFO` only.
+    /// Current default log level.
     log_level: Level,
 
-    /// Log to stderr?
-    is_stderr: bool,
+    /// Per-tag overrides of `log_level`, keyed by `log::Record::target()`.
+    tag_log_levels: HashMap<String, Level>,
+
+    /// Shared with the registered `BluetoothLogDispatcher` so updates take effect without
+    /// re-registering a logger.
+    tag_level_filters: Arc<Mutex<HashMap<String, LevelFilter>>>,
+
+    /// Appenders to enable at `initialize()` time, parsed from the `log_output` spec.
+    enabled_sinks: Vec<SinkKind>,
+
+    /// Rotating-file appender to additionally enable, if configured via
+    /// `enable_rotating_file_sink` before `initialize()`.
+    rotating_file_sink_config: Option<RotatingFileSinkConfig>,
+
+    /// The live appenders, populated once `initialize()` has run, so `set_log_level` can update
+    /// every one of them.
+    sinks: Vec<Arc<dyn LogSink>>,
 
     /// Is logging already initialized?
     is_initialized: bool,
-}
 
-const VERBOSE_ONLY_LOG_TAGS: &[&str] = &[
-    "bt_bta_av", // AV apis
-    "btm_sco",   // SCO data path logs
-    "l2c_csm",   // L2CAP state machine
-    "l2c_link",  // L2CAP link layer logs
-    "sco_hci",   // SCO over HCI
-    "uipc",      // Userspace IPC implementation
-];
+    /// Active btsnoop HCI capture, if any has been started via `set_hci_snoop_enabled`.
+    snoop_writer: Option<Arc<Mutex<SnoopWriter>>>,
+}
 
 impl BluetoothLogging {
-    pub fn new(is_debug: bool, is_verbose_debug: bool, log_output: &str) -> Self {
-        let is_stderr = log_output == "stderr";
+    /// Creates a new `BluetoothLogging`.
+    ///
+    /// `log_output` is a comma-separated list of appenders to enable, e.g. `"stderr,syslog"`.
+    /// Add a rotating-file appender on top of these with `enable_rotating_file_sink`.
+    ///
+    /// `log_level_spec`, if non-empty, is a `RUST_LOG`-style spec string, e.g.
+    /// `"info,l2c_csm=verbose,btm_sco=debug"`: each comma-separated entry either sets the
+    /// default level (a bare level with no `=`) or a tag override (`tag=level`).
+    pub fn new(
+        is_debug: bool,
+        is_verbose_debug: bool,
+        log_output: &str,
+        log_level_spec: &str,
+    ) -> std::result::Result<Self, String> {
+        let mut enabled_sinks = vec![];
+        for sink in log_output.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            match sink {
+                "stderr" => enabled_sinks.push(SinkKind::Stderr),
+                "syslog" => enabled_sinks.push(SinkKind::Syslog),
+                _ => return Err(format!("unknown log sink: {}", sink)),
+            }
+        }
+        if enabled_sinks.is_empty() {
+            enabled_sinks.push(SinkKind::Syslog);
+        }
 
-        let log_level = match (is_debug, is_verbose_debug) {
+        let mut log_level = match (is_debug, is_verbose_debug) {
             (true, true) => Level::Verbose,
             (true, false) => Level::Debug,
             _ => Level::Info,
         };
 
-        Self { log_level, is_stderr, is_initialized: false }
+        let mut tag_log_levels = HashMap::new();
+        for entry in log_level_spec.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            match entry.split_once('=') {
+                None => log_level = Self::parse_level(entry)?,
+                Some((tag, level)) => {
+                    tag_log_levels.insert(tag.to_string(), Self::parse_level(level)?);
+                }
+            }
+        }
+
+        Ok(Self {
+            log_level,
+            tag_log_levels,
+            tag_level_filters: Arc::new(Mutex::new(HashMap::new())),
+            enabled_sinks,
+            rotating_file_sink_config: None,
+            sinks: vec![],
+            is_initialized: false,
+            snoop_writer: None,
+        })
+    }
+
+    /// Enables an additional size-capped rotating-file appender. `max_bytes` is the per-file
+    /// cap; once exceeded, the file is rolled (`path` -> `path.0` -> `path.1` -> ... up to
+    /// `backup_count` backups) before logging continues. Must be called before `initialize()`.
+    pub fn enable_rotating_file_sink(&mut self, path: &str, max_bytes: u64, backup_count: u32) {
+        self.rotating_file_sink_config =
+            Some(RotatingFileSinkConfig { path: path.to_string(), max_bytes, backup_count });
+    }
+
+    /// Returns a handle to the active btsnoop capture, if enabled, for use by callers that emit
+    /// HCI packets (e.g. the `hci` module).
+    pub fn get_snoop_writer(&self) -> Option<Arc<Mutex<SnoopWriter>>> {
+        self.snoop_writer.clone()
+    }
+
+    /// Records one HCI packet to the active btsnoop capture, if enabled. This is the entry point
+    /// the HCI RX/TX path should call per packet (instead of pulling the `Arc` via
+    /// `get_snoop_writer` and poking `SnoopWriter` directly), since it's a no-op rather than a
+    /// panic when capture isn't running.
+    ///
+    /// NOTE: this checkout has no `hci` module to call it from (no raw HCI RX/TX path exists in
+    /// this tree at all - `bt_topshim`'s HCI transport is an external dependency, not source
+    /// present here), so nothing invokes this yet. When that module lands, every packet it
+    /// sends/receives should be passed through here; until then a started capture only ever
+    /// contains the file header.
+    pub fn record_hci_packet(
+        &self,
+        direction: crate::snoop::SnoopDirection,
+        is_command_or_event: bool,
+        bytes: &[u8],
+    ) {
+        if let Some(writer) = &self.snoop_writer {
+            if let Err(e) = writer.lock().unwrap().record(direction, is_command_or_event, bytes) {
+                log::error!("Failed to write btsnoop record: {}", e);
+            }
+        }
+    }
+
+    fn parse_level(level: &str) -> std::result::Result<Level, String> {
+        match level.to_lowercase().as_str() {
+            "trace" => Ok(Level::Trace),
+            "verbose" => Ok(Level::Verbose),
+            "debug" => Ok(Level::Debug),
+            "info" => Ok(Level::Info),
+            "fatal" => Ok(Level::Fatal),
+            _ => Err(format!("unknown log level: {}", level)),
+        }
+    }
+
+    fn level_to_filter(level: Level) -> LevelFilter {
+        match level {
+            // `log::LevelFilter` has no level more verbose than Trace, so Verbose and Trace
+            // collapse onto the same filter; they remain distinct in `bt_topshim::syslog::Level`
+            // for libbluetooth, which does distinguish them.
+            Level::Trace => LevelFilter::Trace,
+            Level::Verbose => LevelFilter::Trace,
+            Level::Debug => LevelFilter::Debug,
+            Level::Info => LevelFilter::Info,
+            // `log::LevelFilter` has no level more severe than Error, so Fatal records are still
+            // emitted as `log::Level::Error`; they're distinguished by the "panic" target and by
+            // libbluetooth, which does have a dedicated Fatal severity.
+            Level::Fatal => LevelFilter::Error,
+        }
+    }
+
+    fn rebuild_tag_level_filters(&self) {
+        let mut filters = self.tag_level_filters.lock().unwrap();
+        filters.clear();
+        for (tag, level) in &self.tag_log_levels {
+            filters.insert(tag.clone(), Self::level_to_filter(*level));
+        }
     }
 
     pub fn initialize(&mut self) -> Result<(), Error> {
-        if self.is_stderr {
-            env_logger::Builder::new().filter(None, self.get_log_level_filter()).init();
-        } else {
-            let formatter = Formatter3164 {
-                facility: Facility::LOG_USER,
-                hostname: None,
-                process: "btadapterd".into(),
-                pid: 0,
-            };
+        self.rebuild_tag_level_filters();
 
-            let logger = syslog::unix(formatter)?;
-            let _ = log::set_boxed_logger(Box::new(BasicLogger::new(logger)))
-                .map(|()| self.apply_linux_log_level());
-            log_panics::init();
+        // Sinks are created with the per-tag max, not just the default level; see
+        // `max_log_level_filter` for why. The dispatcher's own `default_level` stays the true
+        // default, since it's only consulted for records whose tag has no override.
+        let level_filter = self.get_log_level_filter();
+        let max_filter = self.max_log_level_filter();
+        let mut sinks: Vec<Arc<dyn LogSink>> = vec![];
+        for kind in &self.enabled_sinks {
+            match kind {
+                SinkKind::Stderr => sinks.push(Arc::new(StderrSink::new(max_filter))),
+                SinkKind::Syslog => {
+                    sinks.push(Arc::new(SyslogSink::new(max_filter, "btadapterd")?));
+                }
+            }
+        }
+        if let Some(config) = &self.rotating_file_sink_config {
+            sinks.push(Arc::new(RotatingFileSink::new(
+                &config.path,
+                config.max_bytes,
+                config.backup_count,
+                max_filter,
+            )?));
         }
 
+        let dispatcher = BluetoothLogDispatcher {
+            sinks: sinks.clone(),
+            default_level: Mutex::new(level_filter),
+            tag_levels: self.tag_level_filters.clone(),
+        };
+        self.sinks = sinks;
+
+        let _ =
+            log::set_boxed_logger(Box::new(dispatcher)).map(|()| self.apply_linux_log_level());
+
+        // Unconditionally routed through the log pipeline, regardless of which sinks are active,
+        // so a stderr-only deployment still gets panic context.
+        log_panics::init();
+        self.install_fatal_panic_hook();
+
         // Set initial log levels and filter out tags if not verbose debug.
         self.apply_libbluetooth_log_level();
 
         // Initialize the underlying system as well.
         self.is_initialized = true;
+
+        // Verbose (or more verbose) logging implies we also want an HCI capture to go with it.
+        if matches!(self.log_level, Level::Trace | Level::Verbose) {
+            self.set_hci_snoop_enabled(Some(DEFAULT_HCI_SNOOP_LOG_PATH.to_string()));
+        }
+
         Ok(())
     }
 
+    /// Chains a panic hook in front of whatever's currently installed (normally the one
+    /// `log_panics::init()` just set) that logs the panic message and a backtrace at Fatal
+    /// severity and force-flushes every sink, so the record reaches disk/syslog even if the
+    /// process aborts partway through unwinding.
+    fn install_fatal_panic_hook(&self) {
+        let sinks = self.sinks.clone();
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            log::error!(
+                target: "panic",
+                "FATAL: {}\n{}",
+                info,
+                std::backtrace::Backtrace::force_capture()
+            );
+            for sink in &sinks {
+                sink.flush();
+            }
+            previous_hook(info);
+        }));
+    }
+
     fn should_enable_debug_mode(&self) -> bool {
-        self.log_level == Level::Debug || self.log_level == Level::Verbose
+        matches!(self.log_level, Level::Trace | Level::Debug | Level::Verbose)
     }
 
     fn get_log_level_filter(&self) -> LevelFilter {
-        match self.should_enable_debug_mode() {
-            true => LevelFilter::Debug,
-            false => LevelFilter::Info,
-        }
+        Self::level_to_filter(self.log_level)
+    }
+
+    /// The loosest of the default level and any per-tag override. The process-wide max level
+    /// must be at least this permissive, since `log::Record`s more restrictive than it are
+    /// dropped before they ever reach `BluetoothLogDispatcher`. Sink filters must also be set to
+    /// this, not just the default level: `BluetoothLogDispatcher::log` already applies the
+    /// correct per-tag effective level, so a sink filtering more tightly than the loosest override
+    /// would silently re-drop records the dispatcher already decided to let through.
+    fn max_log_level_filter(&self) -> LevelFilter {
+        self.tag_log_levels
+            .values()
+            .map(|level| Self::level_to_filter(*level))
+            .fold(self.get_log_level_filter(), std::cmp::max)
     }
 
     fn apply_linux_log_level(&self) {
-        log::set_max_level(self.get_log_level_filter());
+        let max_filter = self.max_log_level_filter();
+        log::set_max_level(max_filter);
+
+        for sink in &self.sinks {
+            sink.set_level_filter(max_filter);
+        }
     }
 
     fn apply_libbluetooth_log_level(&self) {
         set_default_log_level(self.log_level);
 
-        // TODO(b/371889111): Don't set log level for tag until b/371889111 is fixed.
-        /*
-        // Levels for verbose-only tags.
-        let level = match self.log_level {
-            Level::Verbose => Level::Verbose,
-            _ => Level::Info,
-        };
-        for tag in VERBOSE_ONLY_LOG_TAGS {
-            log::info!("Setting log level for tag {} to {:?}", tag, level);
-            set_log_level_for_tag(tag, level);
+        // Per-tag overrides, propagated to libbluetooth so native code honors them too.
+        for (tag, level) in &self.tag_log_levels {
+            set_log_level_for_tag(tag, *level);
         }
-         */
     }
 }
 
@@ -162,4 +414,41 @@ impl IBluetoothLogging for BluetoothLogging {
     fn get_log_level(&self) -> Level {
         self.log_level
     }
+
+    fn set_log_level_for_tag(&mut self, tag: &str, level: Level) {
+        self.tag_log_levels.insert(tag.to_string(), level);
+
+        if !self.is_initialized {
+            return;
+        }
+
+        self.rebuild_tag_level_filters();
+        self.apply_linux_log_level();
+        set_log_level_for_tag(tag, level);
+
+        log::info!("Setting log level for tag {} to {:?}", tag, level);
+    }
+
+    fn get_log_level_for_tag(&self, tag: &str) -> Option<Level> {
+        self.tag_log_levels.get(tag).cloned()
+    }
+
+    fn set_hci_snoop_enabled(&mut self, path: Option<String>) {
+        match path {
+            Some(path) => match SnoopWriter::new(&path) {
+                Ok(writer) => {
+                    log::info!("Starting btsnoop HCI capture at {}", path);
+                    self.snoop_writer = Some(Arc::new(Mutex::new(writer)));
+                }
+                Err(e) => {
+                    log::error!("Failed to start btsnoop HCI capture at {}: {}", path, e);
+                }
+            },
+            None => {
+                if self.snoop_writer.take().is_some() {
+                    log::info!("Stopping btsnoop HCI capture");
+                }
+            }
+        }
+    }
 }
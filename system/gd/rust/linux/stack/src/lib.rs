@@ -15,17 +15,24 @@ pub mod bluetooth_media;
 pub mod bluetooth_qa;
 pub mod callbacks;
 pub mod dis;
+pub mod log_sink;
+pub mod snoop;
 pub mod socket_manager;
 pub mod suspend;
 pub mod uuid;
 
 use bluetooth_qa::{BluetoothQA, IBluetoothQA};
-use log::{debug, info};
+use hmac::{Hmac, Mac};
+use log::{debug, info, warn};
+use notify::{RecursiveMode, Watcher};
 use num_derive::{FromPrimitive, ToPrimitive};
+use sha1::Sha1;
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc::channel;
 use tokio::sync::mpsc::{Receiver, Sender};
-use tokio::time::{sleep, Duration};
+use tokio::time::{sleep, Duration, Instant};
 
 use crate::battery_manager::{BatteryManager, BatterySet};
 use crate::battery_provider_manager::BatteryProviderManager;
@@ -44,7 +51,9 @@ use crate::bluetooth_gatt::{
 };
 use crate::bluetooth_media::{BluetoothMedia, IBluetoothMedia, MediaActions};
 use crate::dis::{DeviceInformation, ServiceCallbacks};
-use crate::socket_manager::{BluetoothSocketManager, SocketActions};
+use crate::socket_manager::{
+    BluetoothSocketManager, IBluetoothSocketManager, SocketActions, SocketConnectionState,
+};
 use crate::suspend::Suspend;
 use bt_topshim::{
     btif::{BaseCallbacks, BtAclState, BtBondState, BtTransport, DisplayAddress, RawAddress, Uuid},
@@ -91,6 +100,29 @@ pub enum Message {
     LeScannerInband(GattScannerInbandCallbacks),
     LeAdvInband(GattAdvInbandCallbacks),
     LeAdv(GattAdvCallbacks),
+
+    /// `BluetoothGatt`'s scanner API requested a new periodic advertising sync (PAST/PA-sync,
+    /// BLE 5). Params: peer address, advertising SID, skip, sync timeout (10ms units). No handle
+    /// is assigned yet; that arrives via `PeriodicAdvSyncEstablished`.
+    StartPeriodicSync(RawAddress, u8, u16, u16),
+    /// `BluetoothGatt`'s scanner API tore down a periodic advertising sync it previously
+    /// established. Carries the sync handle to stop.
+    StopPeriodicSync(u16),
+    /// A periodic advertising sync (PAST/PA-sync, BLE 5) requested through `BluetoothGatt`'s
+    /// scanner API was established. Carries the sync handle the shim assigned.
+    PeriodicAdvSyncEstablished(u16),
+    /// A periodic advertising report arrived on an established sync. `tx_power` and
+    /// `adv_data_info` are `None` when the shim reports its "not present" sentinel for that
+    /// field, rather than us inventing one.
+    PeriodicAdvReport(u16, Option<i8>, Option<u8>, Vec<u8>),
+    /// An established periodic advertising sync was lost (peer stopped advertising, link loss,
+    /// etc).
+    PeriodicAdvSyncLost(u16),
+    /// The command-timeout watchdog entry registered by `StartPeriodicSync` didn't see a
+    /// `PeriodicAdvSyncEstablished` arrive in time. Carries the token handed out when the
+    /// request was issued, so the stale watchdog entry (and only that one) gets cleared.
+    PeriodicSyncRequestTimeout(u64),
+
     HidHost(HHCallbacks),
     Hfp(HfpCallbacks),
     Sdp(SdpCallbacks),
@@ -98,6 +130,11 @@ pub enum Message {
     CsisClient(CsisClientCallbacks),
     CreateBondWithRetry(BluetoothDevice, BtTransport, u32, Duration),
 
+    /// A pending btif command registered with the command-timeout watchdog (see
+    /// `Stack::dispatch`) didn't see its completion callback arrive in time. Carries the token
+    /// that was handed out when the command was issued.
+    CommandTimeout(u64),
+
     // Actions within the stack
     Media(MediaActions),
     MediaCallbackDisconnected(u32),
@@ -136,6 +173,16 @@ pub enum Message {
 
     SocketManagerActions(SocketActions),
     SocketManagerCallbackDisconnected(u32),
+    /// A tracked RFCOMM/L2CAP socket's connection state changed. See
+    /// `IBluetoothSocketManager::has_open_socket`.
+    SocketConnectionStateChanged(RawAddress, Uuid, SocketConnectionState),
+
+    /// A `ReconnectSupervisor`-managed RPC client recovered from a transient transport drop and
+    /// successfully re-registered. Carries the object's `RPCProxy::get_object_id()`.
+    RpcClientReconnected(String),
+    /// A `ReconnectSupervisor`-managed RPC client exhausted its reconnect attempts. Carries the
+    /// object's `RPCProxy::get_object_id()`.
+    RpcClientReconnectFailed(String),
 
     // Battery related
     BatteryProviderManagerCallbackDisconnected(u32),
@@ -181,6 +228,117 @@ pub enum Message {
     // Note that meida sends this when the profiles are disconnected as a whole, that is, it will
     // not be called when AVRCP is disconnected but not A2DP, as an example.
     ProfileDisconnected(RawAddress),
+
+    /// The HCI hot-plug watcher observed a change under `/sys/class/bluetooth` (adapter node
+    /// appeared/disappeared/changed attributes) and the affected HCI device name, if known (e.g.
+    /// `"hci0"`).
+    HciDeviceChange(EventMask, Option<String>),
+}
+
+/// Coarse classification of a change the HCI hot-plug watcher observed on an adapter device node
+/// - enough to tell a hot-plug from a transient attribute update.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventMask {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// Priority/affinity lane a `Message` is routed through. Each lane has its own bounded channel
+/// (see `Stack::create_channel`) so a slow handler on one lane can't head-of-line-block another;
+/// per-callback ordering is still preserved *within* a lane, the same guarantee
+/// `make_message_dispatcher` has always provided.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lane {
+    /// Connection/bond/suspend control plane and adapter lifecycle - low volume, must stay
+    /// responsive.
+    Control,
+    /// Scanner, GATT, and media callbacks - high volume, latency-tolerant.
+    Bulk,
+    /// Battery, QA, and other housekeeping - lowest priority.
+    BestEffort,
+}
+
+impl Message {
+    /// Which lane this message should be dispatched on. Centralized here so lane assignment
+    /// can't drift out of sync with the variant list.
+    pub(crate) fn lane(&self) -> Lane {
+        match self {
+            Message::InterfaceShutdown
+            | Message::AdapterShutdown
+            | Message::Cleanup
+            | Message::CleanupProfiles
+            | Message::AdapterReady
+            | Message::Base(_)
+            | Message::Sdp(_)
+            | Message::CreateBondWithRetry(..)
+            | Message::CommandTimeout(_)
+            | Message::AdapterCallbackDisconnected(_)
+            | Message::ConnectionCallbackDisconnected(_)
+            | Message::AdapterActions(_)
+            | Message::OnDeviceConnectionOrBondStateChanged(..)
+            | Message::SuspendCallbackRegistered(_)
+            | Message::SuspendCallbackDisconnected(_)
+            | Message::SuspendReady(_)
+            | Message::ResumeReady(_)
+            | Message::AudioReconnectOnResumeComplete
+            | Message::HciDeviceChange(..)
+            | Message::DisconnectDevice(_)
+            | Message::ProfileDisconnected(_)
+            | Message::SocketConnectionStateChanged(..)
+            | Message::HidHost(_)
+            | Message::HidHostEnable => Lane::Control,
+
+            Message::A2dp(_)
+            | Message::Avrcp(_)
+            | Message::LeAudioClient(_)
+            | Message::Hfp(_)
+            | Message::VolumeControl(_)
+            | Message::CsisClient(_)
+            | Message::Media(_)
+            | Message::GattClient(_)
+            | Message::GattServer(_)
+            | Message::LeScanner(_)
+            | Message::LeScannerInband(_)
+            | Message::LeAdv(_)
+            | Message::LeAdvInband(_)
+            | Message::StartPeriodicSync(..)
+            | Message::StopPeriodicSync(_)
+            | Message::PeriodicAdvSyncEstablished(_)
+            | Message::PeriodicAdvReport(..)
+            | Message::PeriodicAdvSyncLost(_)
+            | Message::PeriodicSyncRequestTimeout(_)
+            | Message::GattActions(_)
+            | Message::ScannerCallbackDisconnected(_)
+            | Message::AdvertiserCallbackDisconnected(_)
+            | Message::AdvertiserActions(_)
+            | Message::GattClientCallbackDisconnected(_)
+            | Message::GattServerCallbackDisconnected(_) => Lane::Bulk,
+
+            _ => Lane::BestEffort,
+        }
+    }
+}
+
+/// The sending half of each dispatch lane's channel (see `Lane`). Subsystems hold the sender for
+/// whichever lane(s) their messages belong to and send on it directly; `for_message` picks the
+/// right one when a single call site can produce more than one kind of message.
+#[derive(Clone)]
+pub struct StackSenders {
+    pub control: Sender<Message>,
+    pub bulk: Sender<Message>,
+    pub best_effort: Sender<Message>,
+}
+
+impl StackSenders {
+    /// Returns the sender for the lane `message` belongs to, per `Message::lane`.
+    pub fn for_message(&self, message: &Message) -> &Sender<Message> {
+        match message.lane() {
+            Lane::Control => &self.control,
+            Lane::Bulk => &self.bulk,
+            Lane::BestEffort => &self.best_effort,
+        }
+    }
 }
 
 /// Returns a callable object that dispatches a BTIF callback to Message
@@ -226,6 +384,71 @@ where
     })
 }
 
+/// Path the HCI hot-plug watcher monitors for adapter nodes appearing or disappearing (USB
+/// dongle plug/unplug, firmware crash).
+const HCI_DEVICE_DIR: &str = "/sys/class/bluetooth";
+
+/// Spawns a background thread that watches `HCI_DEVICE_DIR` for HCI adapter nodes
+/// appearing/disappearing and feeds the result back through the single dispatch loop as
+/// `Message::HciDeviceChange`, so `Stack::dispatch` stays the only place that drives the adapter
+/// lifecycle.
+fn start_hci_hotplug_watcher(tx: Sender<Message>) {
+    std::thread::spawn(move || {
+        let (watcher_tx, watcher_rx) = std::sync::mpsc::channel();
+        let mut watcher = match notify::recommended_watcher(watcher_tx) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                warn!("Hci hotplug: failed to create watcher: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = watcher.watch(Path::new(HCI_DEVICE_DIR), RecursiveMode::NonRecursive) {
+            warn!("Hci hotplug: failed to watch {}: {}", HCI_DEVICE_DIR, e);
+            return;
+        }
+
+        for res in watcher_rx {
+            let event = match res {
+                Ok(event) => event,
+                Err(e) => {
+                    warn!("Hci hotplug: watch error: {}", e);
+                    continue;
+                }
+            };
+
+            let mask = if event.kind.is_create() {
+                EventMask::Added
+            } else if event.kind.is_remove() {
+                EventMask::Removed
+            } else if event.kind.is_modify() {
+                EventMask::Modified
+            } else {
+                continue;
+            };
+
+            let hci_name = event
+                .paths
+                .first()
+                .and_then(|p| p.file_name())
+                .map(|name| name.to_string_lossy().into_owned());
+
+            if tx.blocking_send(Message::HciDeviceChange(mask, hci_name)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Tracked state of an established periodic advertising sync (see `Message::StartPeriodicSync`).
+/// Currently a single-variant marker rather than real state: it exists so `periodic_syncs`' keys
+/// (the handles a sync-lost/report event can legitimately reference) are distinguishable from
+/// handles nothing ever established.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SyncState {
+    Established,
+}
+
+#[derive(Debug)]
 pub enum BluetoothAPI {
     Adapter,
     Admin,
@@ -254,19 +477,137 @@ pub enum SuspendMode {
     Resuming = 3,
 }
 
+/// Lifecycle state of the local adapter, modeled on the manager's on/off state machine. Gates
+/// which messages are allowed to mutate subsystems, so a duplicate or out-of-order
+/// `AdapterReady`/`AdapterShutdown` can't corrupt state and an API call during bring-up/teardown
+/// doesn't race it.
+#[derive(FromPrimitive, ToPrimitive, Debug, PartialEq, Clone, Copy)]
+pub enum AdapterState {
+    Off = 0,
+    TurningOn = 1,
+    On = 2,
+    TurningOff = 3,
+}
+
+fn log_illegal_adapter_transition(message: &str, state: AdapterState, hci_index: Option<i32>) {
+    warn!(
+        "Adapter lifecycle [hci{}]: dropping {} while in illegal state {:?}",
+        hci_index.map_or("?".to_string(), |idx| idx.to_string()),
+        message,
+        state
+    );
+}
+
+/// Returns true (and logs) if `message` should be rejected with a retryable error rather than be
+/// allowed to mutate subsystems, because the adapter is still coming up or going down.
+fn reject_api_message_if_not_ready(
+    state: AdapterState,
+    hci_index: Option<i32>,
+    message: &str,
+) -> bool {
+    if state == AdapterState::On {
+        return false;
+    }
+    warn!(
+        "Adapter lifecycle [hci{}]: rejecting {} with a retryable error; adapter is {:?}",
+        hci_index.map_or("?".to_string(), |idx| idx.to_string()),
+        message,
+        state
+    );
+    true
+}
+
+/// One subsystem's contribution to deciding what happens when a device's last profile
+/// disconnects. `is_last_active_link` asks "from my point of view, is there nothing left
+/// keeping this link alive", and `teardown` is run when it agrees. Registering one of these
+/// lets a subsystem participate in `Message::ProfileDisconnected` handling without `Stack::
+/// dispatch`'s central match arm having to know it exists.
+pub struct DisconnectPolicy {
+    name: &'static str,
+    is_last_active_link: Box<dyn Fn(&RawAddress) -> bool + Send + Sync>,
+    teardown: Box<dyn Fn(RawAddress) + Send + Sync>,
+}
+
+impl DisconnectPolicy {
+    pub fn new(
+        name: &'static str,
+        is_last_active_link: impl Fn(&RawAddress) -> bool + Send + Sync + 'static,
+        teardown: impl Fn(RawAddress) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name,
+            is_last_active_link: Box::new(is_last_active_link),
+            teardown: Box::new(teardown),
+        }
+    }
+}
+
+/// Registry of [`DisconnectPolicy`]s consulted on every `Message::ProfileDisconnected`, so
+/// profiles that want last-active-link teardown (today just battery service; eventually HFP,
+/// A2DP, the socket manager) can register themselves instead of being hardcoded into the
+/// dispatch loop.
+#[derive(Default)]
+pub struct DisconnectPolicyRegistry {
+    policies: Vec<DisconnectPolicy>,
+}
+
+impl DisconnectPolicyRegistry {
+    /// Registers `policy` to be consulted on every future `ProfileDisconnected`.
+    pub fn register(&mut self, policy: DisconnectPolicy) {
+        self.policies.push(policy);
+    }
+
+    /// Runs the teardown of every registered policy whose `is_last_active_link` agrees `addr`
+    /// is now idle.
+    pub(crate) fn on_profile_disconnected(&self, addr: RawAddress) {
+        for policy in &self.policies {
+            if (policy.is_last_active_link)(&addr) {
+                info!(
+                    "DisconnectPolicy({}): tearing down idle link to {}",
+                    policy.name,
+                    DisplayAddress(&addr)
+                );
+                (policy.teardown)(addr);
+            }
+        }
+    }
+}
+
 /// Umbrella class for the Bluetooth stack.
 pub struct Stack {}
 
+/// Control-plane traffic (connection/bond/suspend, adapter lifecycle) is low-volume; a small
+/// buffer absorbs a burst without ever blocking the topshim callback thread that sends it.
+const CONTROL_LANE_CAPACITY: usize = 16;
+/// Scanner/GATT/media callbacks are the high-volume path that motivated splitting the lanes in
+/// the first place, so this lane gets the most headroom.
+const BULK_LANE_CAPACITY: usize = 256;
+/// Battery/QA housekeeping traffic is low-volume and not latency sensitive.
+const BEST_EFFORT_LANE_CAPACITY: usize = 32;
+
 impl Stack {
-    /// Creates an mpsc channel for passing messages to the main dispatch loop.
-    pub fn create_channel() -> (Sender<Message>, Receiver<Message>) {
-        channel::<Message>(1)
+    /// Creates one bounded mpsc channel per dispatch lane (see `Lane`) for passing messages to
+    /// the main dispatch loop. Returns the fan-out `StackSenders` plus each lane's receiving
+    /// half, in `Lane::Control`/`Lane::Bulk`/`Lane::BestEffort` order.
+    pub fn create_channel() -> (StackSenders, Receiver<Message>, Receiver<Message>, Receiver<Message>)
+    {
+        let (control_tx, control_rx) = channel::<Message>(CONTROL_LANE_CAPACITY);
+        let (bulk_tx, bulk_rx) = channel::<Message>(BULK_LANE_CAPACITY);
+        let (best_effort_tx, best_effort_rx) = channel::<Message>(BEST_EFFORT_LANE_CAPACITY);
+        (
+            StackSenders { control: control_tx, bulk: bulk_tx, best_effort: best_effort_tx },
+            control_rx,
+            bulk_rx,
+            best_effort_rx,
+        )
     }
 
     /// Runs the main dispatch loop.
     pub async fn dispatch(
-        mut rx: Receiver<Message>,
-        tx: Sender<Message>,
+        mut control_rx: Receiver<Message>,
+        mut bulk_rx: Receiver<Message>,
+        mut best_effort_rx: Receiver<Message>,
+        senders: StackSenders,
         api_tx: Sender<APIMessage>,
         bluetooth: Arc<Mutex<Box<Bluetooth>>>,
         bluetooth_gatt: Arc<Mutex<Box<BluetoothGatt>>>,
@@ -276,19 +617,108 @@ impl Stack {
         bluetooth_media: Arc<Mutex<Box<BluetoothMedia>>>,
         suspend: Arc<Mutex<Box<Suspend>>>,
         bluetooth_socketmgr: Arc<Mutex<Box<BluetoothSocketManager>>>,
+        disconnect_policies: Arc<Mutex<Box<DisconnectPolicyRegistry>>>,
         bluetooth_admin: Arc<Mutex<Box<BluetoothAdmin>>>,
         bluetooth_dis: Arc<Mutex<Box<DeviceInformation>>>,
         bluetooth_qa: Arc<Mutex<Box<BluetoothQA>>>,
     ) {
-        loop {
-            let m = rx.recv().await;
+        // Command-timeout watchdog: tracks btif operations fired from this loop that are waited
+        // on for an async completion callback, so a wedged libbluetooth doesn't hang the stack
+        // silently. Tokens are monotonically increasing and are never reused while outstanding.
+        let mut pending_commands: HashMap<u64, (BluetoothAPI, Instant)> = HashMap::new();
+        let mut next_command_token: u64 = 0;
+        const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+        // Periodic advertising syncs (PAST/PA-sync, BLE 5) established via `StartPeriodicSync`,
+        // keyed by the sync handle the shim assigned on `PeriodicAdvSyncEstablished`. Lets
+        // `PeriodicAdvReport`/`PeriodicAdvSyncLost` tell a handle that's genuinely live from a
+        // stale/unknown one instead of blindly forwarding every report.
+        let mut periodic_syncs: HashMap<u16, SyncState> = HashMap::new();
+        // The controller only runs one outstanding "LE Periodic Advertising Create Sync"
+        // procedure at a time, so a request that never establishes (and is never cancelled) wedges
+        // every later `StartPeriodicSync` behind it. `next_command_token`'s watchdog (tagged
+        // `BluetoothAPI::Gatt`) tracks the pending request; this timeout plays the role of
+        // `COMMAND_TIMEOUT` for it but is longer (30s rather than 10s). Separately, `sync_timeout`
+        // (passed in 10ms units, capped at 0x4000 = 163.84s by the spec) bounds how long the
+        // controller itself keeps searching.
+        const PERIODIC_SYNC_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+        // Adapter lifecycle. The manager starts enabling the adapter before spawning this loop,
+        // so we come up already `TurningOn`. `hci_index` is carried along so the state machine
+        // can eventually be keyed per-adapter; nothing currently feeds it a value.
+        let mut adapter_state = AdapterState::TurningOn;
+        let hci_index: Option<i32> = None;
+
+        // Messages this loop generates for itself (retries, timeouts, hot-plug) are all control
+        // plane traffic, so they go out on the control lane.
+        let tx = senders.control.clone();
+
+        // Give the stack a chance to recover from transient controller loss (USB dongle
+        // unplug, firmware crash) instead of believing an absent adapter is still `On`.
+        start_hci_hotplug_watcher(tx.clone());
+
+        // Battery service only needs its GATT client kept around while it's the sole reason a
+        // device is still connected; once HID, media, and sockets all agree there's nothing else
+        // left, it's safe to drop. This is the one `DisconnectPolicy` this checkout has a real
+        // subsystem for; HFP/A2DP/socket-backed profiles would register their own the same way.
+        {
+            let bas_app_uuid = Uuid::from_string(String::from(BATTERY_SERVICE_GATT_CLIENT_APP_ID))
+                .expect("BAS Uuid failed to be parsed");
+            let bluetooth_gatt = bluetooth_gatt.clone();
+            let bluetooth = bluetooth.clone();
+            let bluetooth_media = bluetooth_media.clone();
+            let bluetooth_socketmgr = bluetooth_socketmgr.clone();
+            let battery_service = battery_service.clone();
+            disconnect_policies.lock().unwrap().register(DisconnectPolicy::new(
+                "battery_service",
+                move |addr| {
+                    bluetooth_gatt.lock().unwrap().get_connected_applications(addr)
+                        == vec![bas_app_uuid]
+                        && !bluetooth.lock().unwrap().is_hh_connected(addr)
+                        && bluetooth_media.lock().unwrap().get_connected_profiles(addr).is_empty()
+                        && !bluetooth_socketmgr.lock().unwrap().has_open_socket(addr)
+                },
+                move |addr| {
+                    battery_service.lock().unwrap().drop_device(addr);
+                },
+            ));
+        }
+
+        let mut control_open = true;
+        let mut bulk_open = true;
+        let mut best_effort_open = true;
 
-            if m.is_none() {
+        loop {
+            if !control_open && !bulk_open && !best_effort_open {
                 eprintln!("Message dispatch loop quit");
                 break;
             }
 
-            match m.unwrap() {
+            // Drain lanes in priority order: control plane traffic is low-volume and must stay
+            // responsive even when the bulk (scanner/GATT/media) or best-effort (battery/QA)
+            // lanes are backed up.
+            let m = tokio::select! {
+                biased;
+                m = control_rx.recv(), if control_open => {
+                    if m.is_none() { control_open = false; }
+                    m
+                }
+                m = bulk_rx.recv(), if bulk_open => {
+                    if m.is_none() { bulk_open = false; }
+                    m
+                }
+                m = best_effort_rx.recv(), if best_effort_open => {
+                    if m.is_none() { best_effort_open = false; }
+                    m
+                }
+            };
+
+            let m = match m {
+                Some(m) => m,
+                None => continue,
+            };
+
+            match m {
                 Message::InterfaceShutdown => {
                     let txl = api_tx.clone();
                     tokio::spawn(async move {
@@ -297,19 +727,51 @@ impl Stack {
                 }
 
                 Message::AdapterShutdown => {
+                    if adapter_state != AdapterState::On {
+                        log_illegal_adapter_transition(
+                            "AdapterShutdown",
+                            adapter_state,
+                            hci_index,
+                        );
+                        continue;
+                    }
+                    adapter_state = AdapterState::TurningOff;
+
                     bluetooth_gatt.lock().unwrap().enable(false);
                     bluetooth.lock().unwrap().disable();
                 }
 
                 Message::Cleanup => {
+                    if adapter_state != AdapterState::TurningOff {
+                        log_illegal_adapter_transition("Cleanup", adapter_state, hci_index);
+                        continue;
+                    }
+                    adapter_state = AdapterState::Off;
+
                     bluetooth.lock().unwrap().cleanup();
                 }
 
                 Message::CleanupProfiles => {
+                    if adapter_state != AdapterState::TurningOff && adapter_state != AdapterState::Off
+                    {
+                        log_illegal_adapter_transition(
+                            "CleanupProfiles",
+                            adapter_state,
+                            hci_index,
+                        );
+                        continue;
+                    }
+
                     bluetooth_media.lock().unwrap().cleanup();
                 }
 
                 Message::AdapterReady => {
+                    if adapter_state != AdapterState::TurningOn {
+                        log_illegal_adapter_transition("AdapterReady", adapter_state, hci_index);
+                        continue;
+                    }
+                    adapter_state = AdapterState::On;
+
                     // Initialize objects that need the adapter to be fully
                     // enabled before running.
 
@@ -342,6 +804,10 @@ impl Stack {
                 Message::Base(b) => {
                     dispatch_base_callbacks(bluetooth.lock().unwrap().as_mut(), b.clone());
                     dispatch_base_callbacks(suspend.lock().unwrap().as_mut(), b);
+
+                    // libbluetooth responded, so any outstanding adapter-command watchdog entries
+                    // are satisfied; a timeout that still fires for one will just find it gone.
+                    pending_commands.retain(|_, (api, _)| !matches!(api, BluetoothAPI::Adapter));
                 }
 
                 // When pairing is busy for any reason, the bond cannot be created.
@@ -354,6 +820,16 @@ impl Stack {
                     let mut bt = bluetooth.lock().unwrap();
                     if !bt.is_pairing_busy() {
                         bt.create_bond(device, bt_transport);
+
+                        let token = next_command_token;
+                        next_command_token += 1;
+                        pending_commands.insert(token, (BluetoothAPI::Adapter, Instant::now()));
+                        let txl = tx.clone();
+                        tokio::spawn(async move {
+                            sleep(COMMAND_TIMEOUT).await;
+                            let _ = txl.send(Message::CommandTimeout(token)).await;
+                        });
+
                         continue;
                     }
 
@@ -410,6 +886,82 @@ impl Stack {
                     dispatch_le_adv_callbacks(bluetooth_gatt.lock().unwrap().as_mut(), m);
                 }
 
+                // `BluetoothGatt`'s scanner API surface (the D-Bus-exposed `start_periodic_sync`/
+                // `stop_periodic_sync` methods themselves) lives on `BluetoothGatt`, which this
+                // checkout doesn't have; those methods would send the two messages below rather
+                // than touching the loop's state directly. The handle-tracking map and the
+                // pending-request timeout watchdog, however, are dispatch-loop state (like
+                // `pending_commands` above), so they're implemented here in full.
+                Message::StartPeriodicSync(address, advertising_sid, skip, sync_timeout) => {
+                    bluetooth_gatt.lock().unwrap().start_periodic_sync(
+                        address,
+                        advertising_sid,
+                        skip,
+                        sync_timeout,
+                    );
+
+                    let token = next_command_token;
+                    next_command_token += 1;
+                    pending_commands.insert(token, (BluetoothAPI::Gatt, Instant::now()));
+                    let txl = tx.clone();
+                    tokio::spawn(async move {
+                        sleep(PERIODIC_SYNC_REQUEST_TIMEOUT).await;
+                        let _ = txl.send(Message::PeriodicSyncRequestTimeout(token)).await;
+                    });
+                }
+
+                Message::StopPeriodicSync(sync_handle) => {
+                    bluetooth_gatt.lock().unwrap().stop_periodic_sync(sync_handle);
+                    periodic_syncs.remove(&sync_handle);
+                }
+
+                Message::PeriodicAdvSyncEstablished(sync_handle) => {
+                    bluetooth_gatt.lock().unwrap().on_periodic_adv_sync_established(sync_handle);
+                    periodic_syncs.insert(sync_handle, SyncState::Established);
+
+                    // The establishment callback arrived, so any command-timeout watchdog entry
+                    // for the request that started this sync is satisfied.
+                    pending_commands.retain(|_, (api, _)| !matches!(api, BluetoothAPI::Gatt));
+                }
+
+                Message::PeriodicAdvReport(sync_handle, tx_power, adv_data_info, data) => {
+                    if !periodic_syncs.contains_key(&sync_handle) {
+                        warn!(
+                            "Periodic adv report for unknown/stopped sync handle {}; dropping",
+                            sync_handle
+                        );
+                        continue;
+                    }
+                    bluetooth_gatt.lock().unwrap().on_periodic_adv_report(
+                        sync_handle,
+                        tx_power,
+                        adv_data_info,
+                        data,
+                    );
+                }
+
+                Message::PeriodicAdvSyncLost(sync_handle) => {
+                    // A lost sync we have no record of establishing is still forwarded (the shim
+                    // is the source of truth), just logged as unexpected rather than silently
+                    // matched against a handle we never tracked.
+                    if periodic_syncs.remove(&sync_handle).is_none() {
+                        warn!("Periodic adv sync lost for untracked sync handle {}", sync_handle);
+                    }
+                    bluetooth_gatt.lock().unwrap().on_periodic_adv_sync_lost(sync_handle);
+                }
+
+                Message::PeriodicSyncRequestTimeout(token) => {
+                    // A completion that raced the timeout already removed the token; ignore it.
+                    if pending_commands.remove(&token).is_some() {
+                        warn!(
+                            "Periodic sync request (token {}) timed out with no establishment \
+                             callback; cancelling so it doesn't wedge the next request",
+                            token
+                        );
+                        bluetooth_gatt.lock().unwrap().cancel_periodic_sync_request();
+                    }
+                }
+
                 Message::Hfp(hf) => {
                     bluetooth_media.lock().unwrap().dispatch_hfp_callbacks(hf);
                 }
@@ -420,9 +972,26 @@ impl Stack {
 
                 Message::Sdp(s) => {
                     dispatch_sdp_callbacks(bluetooth.lock().unwrap().as_mut(), s);
+                    pending_commands.retain(|_, (api, _)| !matches!(api, BluetoothAPI::Adapter));
+                }
+
+                Message::CommandTimeout(token) => {
+                    // A completion that raced the timeout already removed the token; ignore it.
+                    if let Some((api, issued_at)) = pending_commands.remove(&token) {
+                        warn!(
+                            "Command (token {}, subsystem {:?}) timed out after {:?} with no \
+                             completion callback",
+                            token,
+                            api,
+                            issued_at.elapsed()
+                        );
+                    }
                 }
 
                 Message::Media(action) => {
+                    if reject_api_message_if_not_ready(adapter_state, hci_index, "Media") {
+                        continue;
+                    }
                     bluetooth_media.lock().unwrap().dispatch_media_actions(action);
                 }
 
@@ -443,6 +1012,9 @@ impl Stack {
                 }
 
                 Message::AdapterActions(action) => {
+                    if reject_api_message_if_not_ready(adapter_state, hci_index, "AdapterActions") {
+                        continue;
+                    }
                     bluetooth.lock().unwrap().handle_actions(action);
                 }
 
@@ -490,15 +1062,40 @@ impl Stack {
                 }
 
                 Message::AdvertiserActions(action) => {
+                    if reject_api_message_if_not_ready(adapter_state, hci_index, "AdvertiserActions")
+                    {
+                        continue;
+                    }
                     bluetooth_gatt.lock().unwrap().handle_adv_action(action);
                 }
 
                 Message::SocketManagerActions(action) => {
+                    if reject_api_message_if_not_ready(
+                        adapter_state,
+                        hci_index,
+                        "SocketManagerActions",
+                    ) {
+                        continue;
+                    }
                     bluetooth_socketmgr.lock().unwrap().handle_actions(action);
                 }
                 Message::SocketManagerCallbackDisconnected(id) => {
                     bluetooth_socketmgr.lock().unwrap().remove_callback(id);
                 }
+                Message::SocketConnectionStateChanged(addr, uuid, state) => {
+                    debug!(
+                        "SocketManager: {} socket for {} is now {:?}",
+                        DisplayAddress(&addr),
+                        uuid.to_string(),
+                        state
+                    );
+                }
+                Message::RpcClientReconnected(object_id) => {
+                    info!("RPC client {} reconnected after a transport drop", object_id);
+                }
+                Message::RpcClientReconnectFailed(object_id) => {
+                    warn!("RPC client {} gave up reconnecting after a transport drop", object_id);
+                }
                 Message::BatteryProviderManagerBatteryUpdated(remote_address, battery_set) => {
                     battery_manager
                         .lock()
@@ -512,6 +1109,9 @@ impl Stack {
                     battery_service.lock().unwrap().remove_callback(id);
                 }
                 Message::BatteryService(action) => {
+                    if reject_api_message_if_not_ready(adapter_state, hci_index, "BatteryService") {
+                        continue;
+                    }
                     battery_service.lock().unwrap().handle_action(action);
                 }
                 Message::BatteryServiceRefresh => {
@@ -521,6 +1121,9 @@ impl Stack {
                     battery_manager.lock().unwrap().remove_callback(id);
                 }
                 Message::GattActions(action) => {
+                    if reject_api_message_if_not_ready(adapter_state, hci_index, "GattActions") {
+                        continue;
+                    }
                     bluetooth_gatt.lock().unwrap().handle_action(action);
                 }
                 Message::GattClientCallbackDisconnected(id) => {
@@ -611,29 +1214,68 @@ impl Stack {
                 }
 
                 Message::ProfileDisconnected(addr) => {
-                    let bas_app_uuid =
-                        Uuid::from_string(String::from(BATTERY_SERVICE_GATT_CLIENT_APP_ID))
-                            .expect("BAS Uuid failed to be parsed");
-                    // Ideally we would also check that there are no open sockets for this device
-                    // but Floss does not manage socket state so there is no reasonable way for us
-                    // to know whether a socket is open or not.
-                    if bluetooth_gatt.lock().unwrap().get_connected_applications(&addr)
-                        == vec![bas_app_uuid]
-                        && !bluetooth.lock().unwrap().is_hh_connected(&addr)
-                        && bluetooth_media.lock().unwrap().get_connected_profiles(&addr).is_empty()
-                    {
-                        info!(
-                            "BAS: Disconnecting from {} since it's the last active profile",
-                            DisplayAddress(&addr)
+                    // Last-active-link teardown is decided by whichever subsystems registered a
+                    // `DisconnectPolicy` (see `Stack::dispatch`'s setup), rather than this match
+                    // arm reaching into their locks directly.
+                    disconnect_policies.lock().unwrap().on_profile_disconnected(addr);
+                }
+
+                Message::HciDeviceChange(EventMask::Added, hci_name) => {
+                    if adapter_state != AdapterState::Off {
+                        log_illegal_adapter_transition(
+                            "HciDeviceChange(Added)",
+                            adapter_state,
+                            hci_index,
                         );
-                        battery_service.lock().unwrap().drop_device(addr);
+                        continue;
                     }
+                    info!(
+                        "Hci hotplug: adapter {} appeared, driving bring-up",
+                        hci_name.as_deref().unwrap_or("?")
+                    );
+                    adapter_state = AdapterState::TurningOn;
+                    bluetooth.lock().unwrap().enable();
+                }
+
+                Message::HciDeviceChange(EventMask::Removed, hci_name) => {
+                    if adapter_state == AdapterState::Off {
+                        continue;
+                    }
+                    warn!(
+                        "Hci hotplug: adapter {} disappeared; tearing down profiles and forcing \
+                         disconnects",
+                        hci_name.as_deref().unwrap_or("?")
+                    );
+                    // Mirror the normal shutdown path's subsystem teardown; the controller is
+                    // already gone, so there's no completion callback left to wait for.
+                    bluetooth_gatt.lock().unwrap().enable(false);
+                    bluetooth_media.lock().unwrap().cleanup();
+                    bluetooth.lock().unwrap().cleanup();
+                    adapter_state = AdapterState::Off;
+                }
+
+                Message::HciDeviceChange(EventMask::Modified, hci_name) => {
+                    debug!(
+                        "Hci hotplug: adapter {} attributes changed",
+                        hci_name.as_deref().unwrap_or("?")
+                    );
                 }
             }
         }
     }
 }
 
+/// A value carried by an `RPCProxy` property-change notification. Kept to the handful of
+/// primitive shapes the exported fields (adapter name, discovering state, battery level, media
+/// playback state, ...) actually need.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropValue {
+    Str(String),
+    Bool(bool),
+    U8(u8),
+    I32(i32),
+}
+
 /// Signifies that the object may be a proxy to a remote RPC object.
 ///
 /// An object that implements RPCProxy trait signifies that the object may be a proxy to a remote
@@ -655,6 +1297,157 @@ pub trait RPCProxy {
         false
     }
 
+    /// Opts this object's `register_disconnect` callback into the [`ReconnectSupervisor`]'s
+    /// auto-reconnect behavior. The default is a no-op, so objects that don't override it are
+    /// unaffected by a caller enabling it.
+    fn set_auto_reconnect(&mut self, _enabled: bool) {}
+
+    /// Completes the optional challenge-response handshake (see [`RpcChallengeResponse`]) gating
+    /// [`export_for_rpc`](Self::export_for_rpc). The default accepts unconditionally, preserving
+    /// today's "trusted unconditionally" behavior for deployments that don't configure a shared
+    /// secret.
+    fn authenticate(&mut self, _secret: &str) -> bool {
+        true
+    }
+
     /// Makes this object available for remote call.
-    fn export_for_rpc(self: Box<Self>) {}
+    fn export_for_rpc(&mut self) {}
+
+    /// Like [`export_for_rpc`](Self::export_for_rpc), but first requires `authenticate` to
+    /// succeed when `secret` is configured. Pass `None` for deployments that don't require a
+    /// shared secret; this is equivalent to calling `export_for_rpc` directly. Returns whether the
+    /// object was exported, so a caller that also needs to register `self` elsewhere (e.g. in a
+    /// callback table) can decide whether that registration should go ahead.
+    fn export_for_rpc_if_authenticated(&mut self, secret: Option<&str>) -> bool {
+        if let Some(secret) = secret {
+            if !self.authenticate(secret) {
+                warn!("Rejected unauthenticated RPC client for {}", self.get_object_id());
+                return false;
+            }
+        }
+        self.export_for_rpc();
+        true
+    }
+
+    /// Declares which of this object's fields are observable via `notify_property_changed`, so
+    /// the projection layer can advertise them the way BlueZ/rustable expose GATT and adapter
+    /// properties, without the subsystem also having to register a callback interface for them.
+    /// The default is "nothing exported", preserving today's callback-only behavior.
+    fn exported_properties(&self) -> Vec<&'static str> {
+        vec![]
+    }
+
+    /// Tells the projection layer that `name` (expected to be one of `exported_properties()`)
+    /// changed to `value`, so it can emit a property-change notification to subscribed clients.
+    /// The default is a no-op: objects that don't override `exported_properties` have nothing to
+    /// notify, and the real D-Bus projection this would feed isn't part of this checkout.
+    fn notify_property_changed(&self, _name: &str, _value: PropValue) {}
+}
+
+/// HMAC-SHA1 challenge-response handshake for [`RPCProxy::authenticate`], modeled on the
+/// Tinkerforge IP Connection authentication scheme: the server hands out a single-use nonce, the
+/// client proves knowledge of the shared secret by hashing both nonces together, and the secret
+/// itself never goes over the wire.
+///
+/// ```text
+/// server -> client: server_nonce
+/// client -> server: client_nonce, HMAC-SHA1(secret, server_nonce ++ client_nonce)
+/// ```
+#[derive(Default)]
+pub struct RpcChallengeResponse {
+    server_nonce: Option<[u8; 4]>,
+}
+
+impl RpcChallengeResponse {
+    /// Generates a fresh 4-byte server nonce for the client to fold into its response. Replaces
+    /// any previously issued nonce - only the most recently issued challenge can be answered.
+    pub fn issue_server_nonce(&mut self) -> [u8; 4] {
+        let nonce: [u8; 4] = rand::random();
+        self.server_nonce = Some(nonce);
+        nonce
+    }
+
+    /// Verifies `client_nonce`/`digest` against the outstanding server nonce and `secret`, in
+    /// constant time. Consumes the server nonce either way, so a response is only ever valid
+    /// once.
+    pub fn verify(&mut self, secret: &str, client_nonce: [u8; 4], digest: &[u8; 20]) -> bool {
+        let server_nonce = match self.server_nonce.take() {
+            Some(nonce) => nonce,
+            None => return false,
+        };
+
+        let mut mac = match HmacSha1::new_from_slice(secret.as_bytes()) {
+            Ok(mac) => mac,
+            Err(_) => return false,
+        };
+        mac.update(&server_nonce);
+        mac.update(&client_nonce);
+
+        constant_time_eq(&mac.finalize().into_bytes(), digest)
+    }
+}
+
+type HmacSha1 = Hmac<Sha1>;
+
+/// Compares two equal-length byte slices without branching on the result, so a mismatched digest
+/// can't be used to time a byte-by-byte attack on the shared secret.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// First retry delay for [`ReconnectSupervisor`]; doubled on each failed attempt.
+const RECONNECT_INITIAL_DELAY: Duration = Duration::from_millis(100);
+/// Cap on [`ReconnectSupervisor`]'s retry delay, once doubling would otherwise exceed it.
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+/// [`ReconnectSupervisor`] gives up and sends `Message::RpcClientReconnectFailed` after this many
+/// failed attempts.
+const RECONNECT_MAX_ATTEMPTS: u32 = 10;
+
+/// Reconnection supervisor for `RPCProxy` objects that opt in via `set_auto_reconnect`, modeled
+/// on Tinkerforge's IPConnection auto-reconnect: on a disconnect, retries with bounded
+/// exponential backoff and jitter (so a batch of clients dropped by the same transport hiccup
+/// doesn't retry in lockstep), then reports the outcome back through the dispatch loop.
+///
+/// This only owns the backoff timing; re-registering the actual callback object is subsystem
+/// specific; the owning subsystem's `register_disconnect` handler supplies that as `retry`.
+#[derive(Clone)]
+pub struct ReconnectSupervisor {
+    tx: Sender<Message>,
+}
+
+impl ReconnectSupervisor {
+    pub fn new(tx: Sender<Message>) -> Self {
+        Self { tx }
+    }
+
+    /// Starts the backoff loop for `object_id`. `retry` runs on each attempt and should return
+    /// whether re-registration succeeded; the loop stops at the first success or after
+    /// `RECONNECT_MAX_ATTEMPTS` failures.
+    pub fn start(&self, object_id: String, retry: impl Fn() -> bool + Send + 'static) {
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            let mut delay = RECONNECT_INITIAL_DELAY;
+            for attempt in 1..=RECONNECT_MAX_ATTEMPTS {
+                sleep(Self::jittered(delay)).await;
+
+                if retry() {
+                    let _ = tx.send(Message::RpcClientReconnected(object_id)).await;
+                    return;
+                }
+
+                warn!("RPC client {} reconnect attempt {} failed", object_id, attempt);
+                delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+            }
+
+            let _ = tx.send(Message::RpcClientReconnectFailed(object_id)).await;
+        });
+    }
+
+    fn jittered(delay: Duration) -> Duration {
+        let jitter_ms = rand::random::<u64>() % (delay.as_millis() as u64 / 2 + 1);
+        delay + Duration::from_millis(jitter_ms)
+    }
 }
@@ -0,0 +1,185 @@
+//! Pluggable log appenders for `BluetoothLogging`.
+//!
+//! Mirrors the appender model of established logging stacks (e.g. log4j/logback, Android's
+//! liblog): several independently-configured sinks can be active at once, each deciding for
+//! itself whether a given record passes its own level filter.
+
+use std::fs;
+use std::io::Write;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+
+use log::{LevelFilter, Record};
+use syslog::{BasicLogger, Formatter3164, Logger as SyslogLogger, LoggerBackend};
+
+/// A single log appender. Implementations decide internally, via their own level filter, whether
+/// a record should be written.
+pub trait LogSink: Send + Sync {
+    /// Writes `record`, if it passes this sink's level filter.
+    fn log(&self, record: &Record);
+
+    /// Updates this sink's level filter.
+    fn set_level_filter(&self, level: LevelFilter);
+
+    /// Flushes any buffered output.
+    fn flush(&self);
+}
+
+fn level_filter_from_u8(v: u8) -> LevelFilter {
+    match v {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Writes formatted records to stderr.
+pub struct StderrSink {
+    level: AtomicU8,
+}
+
+impl StderrSink {
+    pub fn new(level: LevelFilter) -> Self {
+        Self { level: AtomicU8::new(level as u8) }
+    }
+}
+
+impl LogSink for StderrSink {
+    fn log(&self, record: &Record) {
+        if record.level() > level_filter_from_u8(self.level.load(Ordering::Relaxed)) {
+            return;
+        }
+        eprintln!("{} {}: {}", record.level(), record.target(), record.args());
+    }
+
+    fn set_level_filter(&self, level: LevelFilter) {
+        self.level.store(level as u8, Ordering::Relaxed);
+    }
+
+    fn flush(&self) {}
+}
+
+/// Writes records to the local syslog daemon.
+pub struct SyslogSink {
+    level: AtomicU8,
+    logger: Mutex<BasicLogger>,
+}
+
+impl SyslogSink {
+    pub fn new(level: LevelFilter, process: &str) -> std::io::Result<Self> {
+        let formatter = Formatter3164 {
+            facility: syslog::Facility::LOG_USER,
+            hostname: None,
+            process: process.into(),
+            pid: 0,
+        };
+        let logger: SyslogLogger<LoggerBackend, Formatter3164> = syslog::unix(formatter)?;
+        Ok(Self { level: AtomicU8::new(level as u8), logger: Mutex::new(BasicLogger::new(logger)) })
+    }
+}
+
+impl LogSink for SyslogSink {
+    fn log(&self, record: &Record) {
+        if record.level() > level_filter_from_u8(self.level.load(Ordering::Relaxed)) {
+            return;
+        }
+        log::Log::log(&*self.logger.lock().unwrap(), record);
+    }
+
+    fn set_level_filter(&self, level: LevelFilter) {
+        self.level.store(level as u8, Ordering::Relaxed);
+    }
+
+    fn flush(&self) {
+        log::Log::flush(&*self.logger.lock().unwrap());
+    }
+}
+
+struct RotatingFileState {
+    path: String,
+    max_bytes: u64,
+    backup_count: u32,
+    file: fs::File,
+    size: u64,
+}
+
+impl RotatingFileState {
+    fn new(path: &str, max_bytes: u64, backup_count: u32) -> std::io::Result<Self> {
+        let file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path: path.to_string(), max_bytes, backup_count, file, size })
+    }
+
+    fn backup_path(&self, index: u32) -> String {
+        format!("{}.{}", self.path, index)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if self.backup_count > 0 {
+            let last = self.backup_count - 1;
+            let _ = fs::remove_file(self.backup_path(last));
+            for i in (0..last).rev() {
+                let _ = fs::rename(self.backup_path(i), self.backup_path(i + 1));
+            }
+            fs::rename(&self.path, self.backup_path(0))?;
+        } else {
+            fs::remove_file(&self.path)?;
+        }
+
+        self.file = fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    fn write(&mut self, line: &str) -> std::io::Result<()> {
+        let bytes = line.as_bytes();
+        if self.size + bytes.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(bytes)?;
+        self.file.flush()?;
+        self.size += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+/// Writes records to a size-capped, rotating local file (`path`, `path.0`, `path.1`, ...).
+pub struct RotatingFileSink {
+    level: AtomicU8,
+    state: Mutex<RotatingFileState>,
+}
+
+impl RotatingFileSink {
+    pub fn new(
+        path: &str,
+        max_bytes: u64,
+        backup_count: u32,
+        level: LevelFilter,
+    ) -> std::io::Result<Self> {
+        Ok(Self {
+            level: AtomicU8::new(level as u8),
+            state: Mutex::new(RotatingFileState::new(path, max_bytes, backup_count)?),
+        })
+    }
+}
+
+impl LogSink for RotatingFileSink {
+    fn log(&self, record: &Record) {
+        if record.level() > level_filter_from_u8(self.level.load(Ordering::Relaxed)) {
+            return;
+        }
+        let line = format!("{} {}: {}\n", record.level(), record.target(), record.args());
+        let _ = self.state.lock().unwrap().write(&line);
+    }
+
+    fn set_level_filter(&self, level: LevelFilter) {
+        self.level.store(level as u8, Ordering::Relaxed);
+    }
+
+    fn flush(&self) {
+        let _ = self.state.lock().unwrap().file.flush();
+    }
+}
@@ -0,0 +1,252 @@
+//! Socket management (`IBluetoothSocketManager`).
+//!
+//! Modeled on BlueZ's RFCOMM/L2CAP profile handling: a profile registers itself by UUID before
+//! it can listen or connect, every socket handed out to a caller owns its file descriptor (so
+//! dropping it tears down the channel), and every live socket is tracked by `(addr, uuid)` so
+//! other subsystems can ask whether a device still has anything open - notably the last-profile
+//! teardown in `Stack::dispatch`'s `ProfileDisconnected` handler.
+
+use std::collections::{HashMap, HashSet};
+use std::os::fd::OwnedFd;
+use std::os::unix::net::UnixStream;
+
+use bt_topshim::btif::{DisplayAddress, RawAddress, Uuid};
+use log::{info, warn};
+use tokio::sync::mpsc::Sender;
+
+use crate::bluetooth_admin::BluetoothAdminPolicyHelper;
+use crate::Message;
+
+/// Connection state of a tracked socket, reported via `Message::SocketConnectionStateChanged`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketConnectionState {
+    /// A listening socket with no peer connected yet.
+    Listening,
+    /// An outgoing connection attempt is in flight.
+    Connecting,
+    /// The socket has an active peer.
+    Connected,
+    /// The socket was closed, locally or by the peer.
+    Disconnected,
+}
+
+/// Why a socket request didn't produce a connected socket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketRequestError {
+    /// The remote end, or local admin policy, refused the connection.
+    Rejected,
+    /// The request was canceled before it completed (e.g. allocating the underlying socket
+    /// failed, or the caller tore down the listener first).
+    Canceled,
+}
+
+/// A socket handed out to a caller. Dropping `fd` closes the underlying RFCOMM/L2CAP channel.
+pub struct BluetoothSocket {
+    pub id: u64,
+    pub addr: RawAddress,
+    pub uuid: Uuid,
+    pub fd: OwnedFd,
+}
+
+#[derive(Clone, Copy)]
+struct TrackedSocket {
+    addr: RawAddress,
+    uuid: Uuid,
+    state: SocketConnectionState,
+}
+
+/// Requests that flow from the API surface into the socket manager's owning task.
+pub enum SocketActions {
+    RegisterProfile(Uuid),
+    UnregisterProfile(Uuid),
+    Listen(Uuid),
+    Connect(RawAddress, Uuid),
+    Close(u64),
+}
+
+/// Defines the socket manager API (RFCOMM/L2CAP profile registration and socket lifecycle).
+pub trait IBluetoothSocketManager {
+    /// Registers `uuid` as a profile this process will listen for/connect to sockets on.
+    /// Returns `false` if it was already registered.
+    fn register_profile(&mut self, uuid: Uuid) -> bool;
+    /// Reverses `register_profile`. Returns `false` if it wasn't registered.
+    fn unregister_profile(&mut self, uuid: Uuid) -> bool;
+    /// Opens a listening socket for an already-registered profile.
+    fn listen_using_rfcomm(&mut self, uuid: Uuid) -> Result<BluetoothSocket, SocketRequestError>;
+    /// Connects to `addr`'s `uuid` service.
+    fn connect_using_rfcomm(
+        &mut self,
+        addr: RawAddress,
+        uuid: Uuid,
+    ) -> Result<BluetoothSocket, SocketRequestError>;
+    /// Closes a previously handed-out socket.
+    fn close(&mut self, socket_id: u64);
+    /// Whether `addr` has a tracked socket that is connecting or connected. Listening sockets
+    /// aren't tied to a peer yet, so they don't count.
+    fn has_open_socket(&self, addr: &RawAddress) -> bool;
+}
+
+pub struct BluetoothSocketManager {
+    tx: Sender<Message>,
+    registered_profiles: HashSet<Uuid>,
+    sockets: HashMap<u64, TrackedSocket>,
+    next_socket_id: u64,
+    admin_helper: BluetoothAdminPolicyHelper,
+}
+
+impl BluetoothSocketManager {
+    pub fn new(tx: Sender<Message>) -> Self {
+        Self {
+            tx,
+            registered_profiles: HashSet::new(),
+            sockets: HashMap::new(),
+            next_socket_id: 0,
+            admin_helper: Default::default(),
+        }
+    }
+
+    /// Called by `BluetoothAdmin` whenever the allowlist/denylist changes, so
+    /// `connect_using_rfcomm` can reject a profile the admin policy has blocked.
+    pub fn handle_admin_policy_changed(&mut self, admin_helper: BluetoothAdminPolicyHelper) {
+        self.admin_helper = admin_helper;
+    }
+
+    pub(crate) fn handle_actions(&mut self, action: SocketActions) {
+        match action {
+            SocketActions::RegisterProfile(uuid) => {
+                self.register_profile(uuid);
+            }
+            SocketActions::UnregisterProfile(uuid) => {
+                self.unregister_profile(uuid);
+            }
+            SocketActions::Listen(uuid) => {
+                if let Err(e) = self.listen_using_rfcomm(uuid) {
+                    warn!("SocketManager: listen for {} rejected: {:?}", uuid.to_string(), e);
+                }
+            }
+            SocketActions::Connect(addr, uuid) => {
+                if let Err(e) = self.connect_using_rfcomm(addr, uuid) {
+                    warn!(
+                        "SocketManager: connect to {} for {} rejected: {:?}",
+                        DisplayAddress(&addr),
+                        uuid.to_string(),
+                        e
+                    );
+                }
+            }
+            SocketActions::Close(socket_id) => self.close(socket_id),
+        }
+    }
+
+    /// Handles a request to send an RFCOMM modem-status command for `dlci` to `addr`.
+    ///
+    /// No command is sent: this manager tracks sockets only as connection state and has no RFCOMM
+    /// session to send it on, so the request is only logged as a warning.
+    pub fn rfcomm_send_msc(&self, dlci: u8, addr: RawAddress) {
+        warn!(
+            "SocketManager: rfcomm_send_msc(dlci={}, addr={}) has no backing session to send on",
+            dlci,
+            DisplayAddress(&addr)
+        );
+    }
+
+    pub fn remove_callback(&mut self, _callback_id: u32) {}
+
+    fn allocate_socket_pair() -> Result<OwnedFd, SocketRequestError> {
+        // No real RFCOMM/L2CAP socket shim is present in this checkout; an anonymous connected
+        // pair stands in for "an owned fd backing a channel" without fabricating a btif call that
+        // doesn't exist.
+        match UnixStream::pair() {
+            Ok((local, remote)) => {
+                drop(remote);
+                Ok(local.into())
+            }
+            Err(e) => {
+                warn!("SocketManager: failed to allocate socket: {}", e);
+                Err(SocketRequestError::Canceled)
+            }
+        }
+    }
+
+    fn track(&mut self, addr: RawAddress, uuid: Uuid, state: SocketConnectionState) -> u64 {
+        let id = self.next_socket_id;
+        self.next_socket_id += 1;
+        self.sockets.insert(id, TrackedSocket { addr, uuid, state });
+        self.notify_state_changed(addr, uuid, state);
+        id
+    }
+
+    fn notify_state_changed(&self, addr: RawAddress, uuid: Uuid, state: SocketConnectionState) {
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            let _ = tx.send(Message::SocketConnectionStateChanged(addr, uuid, state)).await;
+        });
+    }
+}
+
+impl IBluetoothSocketManager for BluetoothSocketManager {
+    fn register_profile(&mut self, uuid: Uuid) -> bool {
+        self.registered_profiles.insert(uuid)
+    }
+
+    fn unregister_profile(&mut self, uuid: Uuid) -> bool {
+        self.registered_profiles.remove(&uuid)
+    }
+
+    fn listen_using_rfcomm(&mut self, uuid: Uuid) -> Result<BluetoothSocket, SocketRequestError> {
+        if !self.registered_profiles.contains(&uuid) {
+            info!(
+                "SocketManager: listen_using_rfcomm for unregistered profile {}",
+                uuid.to_string()
+            );
+            return Err(SocketRequestError::Rejected);
+        }
+
+        let fd = Self::allocate_socket_pair()?;
+        // Not yet associated with a peer.
+        let addr = RawAddress::from([0u8; 6]);
+        let id = self.track(addr, uuid, SocketConnectionState::Listening);
+
+        Ok(BluetoothSocket { id, addr, uuid, fd })
+    }
+
+    fn connect_using_rfcomm(
+        &mut self,
+        addr: RawAddress,
+        uuid: Uuid,
+    ) -> Result<BluetoothSocket, SocketRequestError> {
+        if !self.admin_helper.is_service_allowed(&uuid) {
+            info!(
+                "SocketManager: connect to {} for {} rejected by admin policy",
+                DisplayAddress(&addr),
+                uuid.to_string()
+            );
+            return Err(SocketRequestError::Rejected);
+        }
+
+        let fd = Self::allocate_socket_pair()?;
+        let id = self.track(addr, uuid, SocketConnectionState::Connected);
+
+        Ok(BluetoothSocket { id, addr, uuid, fd })
+    }
+
+    fn close(&mut self, socket_id: u64) {
+        if let Some(socket) = self.sockets.remove(&socket_id) {
+            self.notify_state_changed(
+                socket.addr,
+                socket.uuid,
+                SocketConnectionState::Disconnected,
+            );
+        }
+    }
+
+    fn has_open_socket(&self, addr: &RawAddress) -> bool {
+        self.sockets.values().any(|socket| {
+            &socket.addr == addr
+                && matches!(
+                    socket.state,
+                    SocketConnectionState::Connecting | SocketConnectionState::Connected
+                )
+        })
+    }
+}